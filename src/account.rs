@@ -0,0 +1,265 @@
+use crate::{
+    indexer::{use_block, with_block, BlockRef},
+    network::{network_href, use_network},
+    registered_account::KeyVisibility,
+    DetailRow, DetailsList, DisplayValue, HexVec, LinkedValue,
+};
+use ethers_core::abi::ethabi::ethereum_types::H160;
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+use subxt::utils::AccountId32;
+
+/// SS58 address formats an account ID is shown in, with their prefixes
+pub const SS58_FORMATS: [(&str, u16); 3] = [("Substrate", 42), ("Polkadot", 0), ("Kusama", 2)];
+
+/// A program an account uses, along with the configuration it uses it with
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgramInstance {
+    pub program_pointer: String,
+    pub program_config: HexVec,
+}
+
+/// Everything we know about a registered account
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountDetails {
+    pub account_id: AccountId32,
+    /// The account ID encoded with each of `SS58_FORMATS`
+    pub ss58_addresses: Vec<(String, String)>,
+    pub key_visibility: (String, String),
+    /// Only present for private accounts
+    pub x25519_public_key: Option<HexVec>,
+    pub verifying_key: HexVec,
+    pub uncompressed_public_key: Option<HexVec>,
+    pub ethereum_address: Option<H160>,
+    pub program_modification_account: String,
+    pub programs: Vec<ProgramInstance>,
+    pub block: BlockRef,
+}
+
+#[server(GetAccountDetails, "/api")]
+pub async fn get_account_details(
+    network: String,
+    account_id: String,
+    block: Option<String>,
+) -> Result<Option<AccountDetails>, ServerFnError> {
+    use crate::{block_ref, get_api_rpc};
+    use std::str::FromStr;
+
+    let account_id = AccountId32::from_str(&account_id)
+        .map_err(|_| ServerFnError::Args(format!("Invalid account ID {}", account_id)))?;
+    let (api, _rpc) = get_api_rpc(&network).await?;
+    let block = block_ref(&network, block).await?;
+
+    query_account_details(&api, account_id, block)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[component]
+pub fn AccountPage() -> impl IntoView {
+    let network = use_network();
+    let block = use_block();
+    let params = use_params_map();
+    let details = create_resource(
+        move || (network.get(), params.with(|params| params.get("id").cloned().unwrap_or_default()), block.get()),
+        |(network, id, block)| get_account_details(network, id, block),
+    );
+    view! {
+        <div class="container mx-auto">
+            <A href=with_block(network_href("")) class="text-sm underline">
+                "Back to status page"
+            </A>
+            <A
+                href=move || {
+                    network_href(
+                        &format!("/signing?account={}", params.with(|params| params.get("id").cloned().unwrap_or_default())),
+                    )
+                }
+                class="text-sm underline ml-4"
+            >
+                "Signing activity"
+            </A>
+            <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                {move || {
+                    details
+                        .get()
+                        .map(move |details| match details {
+                            Err(e) => {
+                                view! { <pre class="error">"server error: " {e.to_string()}</pre> }
+                                    .into_view()
+                            }
+                            Ok(None) => view! { <p class="my-4">"This account is not registered."</p> }.into_view(),
+                            Ok(Some(details)) => view! { <AccountDetails details/> }.into_view(),
+                        })
+                        .unwrap_or_default()
+                }}
+
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+pub fn AccountDetails(details: AccountDetails) -> impl IntoView {
+    let ss58_addresses = details
+        .ss58_addresses
+        .into_iter()
+        .map(|(format, address)| {
+            view! {
+                <tr class="hover:bg-gray-200">
+                    <th class="p-4 border-r border-blue-gray-100 bg-blue-50 w-1/4">
+                        <p class="block font-sans text-sm antialiased font-normal leading-none text-blue-gray-900 opacity-70">
+                            {format!("Address ({})", format)}
+                        </p>
+                    </th>
+                    <DisplayValue value=address long_value=None/>
+                </tr>
+            }
+        })
+        .collect_view();
+    let programs = details
+        .programs
+        .into_iter()
+        .map(|program| {
+            view! {
+                <tr class="hover:bg-gray-200">
+                    <LinkedValue
+                        value=program.program_pointer.clone()
+                        href=with_block(network_href(&format!("/program/{}", program.program_pointer)))
+                    />
+                    <DisplayValue
+                        value=format!("0x{:?}", program.program_config)
+                        long_value=None
+                    />
+                </tr>
+            }
+        })
+        .collect_view();
+    view! {
+        <DetailsList title=format!("Account {}", details.account_id)>
+            <DetailRow label="Account ID (hex)">
+                <DisplayValue value=format!("0x{}", hex::encode(details.account_id.0)) long_value=None/>
+            </DetailRow>
+            {ss58_addresses}
+            <DetailRow label="Access Mode">
+                <KeyVisibility key_visibility=details.key_visibility.0 color=details.key_visibility.1/>
+            </DetailRow>
+            {details
+                .x25519_public_key
+                .map(|key| {
+                    view! {
+                        <DetailRow label="X25519 Public Key">
+                            <DisplayValue value=format!("0x{:?}", key) long_value=None/>
+                        </DetailRow>
+                    }
+                })}
+            <DetailRow label="Verifying Key">
+                <DisplayValue value=format!("0x{:?}", details.verifying_key) long_value=None/>
+            </DetailRow>
+            <DetailRow label="Uncompressed Public Key">
+                <DisplayValue
+                    value=details
+                        .uncompressed_public_key
+                        .map(|key| format!("0x{:?}", key))
+                        .unwrap_or("Invalid verifying key".to_string())
+                    long_value=None
+                />
+            </DetailRow>
+            <DetailRow label="Ethereum Address">
+                <DisplayValue
+                    value=details.ethereum_address.map(|e| format!("{:?}", e)).unwrap_or_default()
+                    long_value=None
+                />
+            </DetailRow>
+            <DetailRow label="Program Modification Account">
+                <DisplayValue value=details.program_modification_account long_value=None/>
+            </DetailRow>
+            <DetailRow label="Read at Block">
+                <DisplayValue
+                    value=format!("#{}", details.block.number)
+                    long_value=Some(details.block.hash)
+                />
+            </DetailRow>
+        </DetailsList>
+        <div class="relative flex flex-col w-full h-full text-gray-700 bg-white shadow-md rounded-xl bg-clip-border mt-4">
+            <table class="w-full text-left table-auto min-w-max">
+                <thead>
+                    <tr>
+                        <th class="p-4 border-b border-blue-gray-100 bg-blue-50">"Program"</th>
+                        <th class="p-4 border-b border-blue-gray-100 bg-blue-50">"Configuration"</th>
+                    </tr>
+                </thead>
+                <tbody>{programs}</tbody>
+            </table>
+        </div>
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::registered_account::{public_key_to_eth_address, uncompress_public_key};
+        use entropy_shared::KeyVisibility as ChainKeyVisibility;
+        use entropy_testing_utils::{
+            chain_api::{entropy::runtime_types::pallet_relayer::pallet::RegisteredInfo, EntropyConfig},
+        };
+        use parity_scale_codec::Decode;
+        use subxt::{
+            ext::sp_core::crypto::{AccountId32 as SpAccountId32, Ss58AddressFormat, Ss58Codec},
+            OnlineClient,
+        };
+
+        /// Read the registration details of an account at the given block
+        pub async fn query_account_details(
+            api: &OnlineClient<EntropyConfig>,
+            account_id: AccountId32,
+            block: BlockRef,
+        ) -> anyhow::Result<Option<AccountDetails>> {
+            let storage_address = subxt::dynamic::storage("Relayer", "Registered", vec![account_id.clone()]);
+            let registered_info = match api.storage().at(block.block_hash()?).fetch(&storage_address).await? {
+                Some(registered_info) => RegisteredInfo::decode(&mut registered_info.into_encoded().as_ref())?,
+                None => return Ok(None),
+            };
+
+            let (key_visibility, x25519_public_key) = match registered_info.key_visibility.0 {
+                ChainKeyVisibility::Public => (("Public".to_string(), "green".to_string()), None),
+                ChainKeyVisibility::Permissioned => (("Permissioned".to_string(), "amber".to_string()), None),
+                ChainKeyVisibility::Private(x25519_public_key) => (
+                    ("Private".to_string(), "red".to_string()),
+                    Some(HexVec(x25519_public_key.to_vec())),
+                ),
+            };
+            let verifying_key = registered_info.verifying_key.0;
+
+            Ok(Some(AccountDetails {
+                ss58_addresses: SS58_FORMATS
+                    .iter()
+                    .map(|(name, prefix)| {
+                        (
+                            format!("{} {}", name, prefix),
+                            SpAccountId32::from(account_id.0)
+                                .to_ss58check_with_version(Ss58AddressFormat::custom(*prefix)),
+                        )
+                    })
+                    .collect(),
+                account_id,
+                key_visibility,
+                x25519_public_key,
+                uncompressed_public_key: uncompress_public_key(&verifying_key).ok().map(HexVec),
+                ethereum_address: public_key_to_eth_address(verifying_key.clone()).ok(),
+                verifying_key: HexVec(verifying_key),
+                program_modification_account: registered_info.program_modification_account.to_string(),
+                programs: registered_info
+                    .programs_data
+                    .0
+                    .into_iter()
+                    .map(|program_instance| ProgramInstance {
+                        program_pointer: format!("{:?}", program_instance.program_pointer),
+                        program_config: HexVec(program_instance.program_config),
+                    })
+                    .collect(),
+                block,
+            }))
+        }
+    }
+}
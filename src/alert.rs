@@ -0,0 +1,511 @@
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    use crate::{
+        announcement::AnnouncementStore,
+        chain::unix_now,
+        network::Network,
+        probe::KeyCheck,
+    };
+    use serde::Serialize;
+    use std::{
+        collections::{BTreeSet, HashMap},
+        time::Duration,
+    };
+
+    /// How long to wait for a webhook to accept a notification
+    const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Where notifications go and when they are sent
+    #[derive(Clone, Debug)]
+    pub struct AlertConfig {
+        /// URLs which notifications are posted to
+        pub webhooks: Vec<String>,
+        /// Alert when finality falls this many blocks behind the best block
+        pub finality_lag_blocks: u32,
+        /// Alert when a threshold server has been down for this long
+        pub tss_down_after: Duration,
+        /// How often the alerting rules are evaluated
+        pub evaluation_interval: Duration,
+    }
+
+    /// Something which is currently wrong
+    struct Condition {
+        /// Identifies the condition between evaluations, so that it is only notified once
+        key: String,
+        summary: String,
+        /// How long it must hold before it is notified
+        hold: Duration,
+    }
+
+    /// The outcome of evaluating the rules
+    #[derive(Default)]
+    struct Evaluation {
+        /// The conditions which hold
+        conditions: Vec<Condition>,
+        /// Conditions which could not be checked as their data is missing, so keep whatever state
+        /// they were in. Either a key, or a prefix ending in `:` for a family of keys.
+        unchecked: Vec<String>,
+    }
+
+    impl Evaluation {
+        fn is_unchecked(&self, key: &str) -> bool {
+            self.unchecked
+                .iter()
+                .any(|unchecked| key == unchecked || (unchecked.ends_with(':') && key.starts_with(unchecked.as_str())))
+        }
+    }
+
+    /// A message posted to each webhook. `text` is read by Slack and Matrix and `content` by
+    /// Discord.
+    #[derive(Clone, Debug, Serialize)]
+    pub struct Notification {
+        /// Name of the network the condition is on
+        pub network: String,
+        /// `firing`, `resolved` or `changed`
+        pub status: &'static str,
+        /// Which condition this is about
+        pub alert: String,
+        pub summary: String,
+        pub text: String,
+        pub content: String,
+        /// Unix time in seconds
+        pub time: u64,
+    }
+
+    impl Notification {
+        fn new(network: &str, label: &str, status: &'static str, alert: &str, summary: &str) -> Self {
+            let text = format!("[{}] {}: {}", status.to_uppercase(), label, summary);
+            Notification {
+                network: network.to_string(),
+                status,
+                alert: alert.to_string(),
+                summary: summary.to_string(),
+                content: text.clone(),
+                text,
+                time: unix_now(),
+            }
+        }
+    }
+
+    /// What has already been notified, so that each condition is notified once when it starts
+    /// and once when it recovers
+    struct AlertState {
+        /// Name and label of the network, which notifications are about
+        network: String,
+        label: String,
+        /// Unix time in seconds each current condition was first seen
+        pending: HashMap<String, u64>,
+        /// Summaries of the conditions which have been notified
+        firing: HashMap<String, String>,
+        /// Stash accounts of the validator set at the last evaluation
+        validators: Option<BTreeSet<String>>,
+    }
+
+    impl AlertState {
+        fn new(network: &str, label: &str) -> Self {
+            AlertState {
+                network: network.to_string(),
+                label: label.to_string(),
+                pending: HashMap::new(),
+                firing: HashMap::new(),
+                validators: None,
+            }
+        }
+
+        /// Work out which notifications to send given the conditions which currently hold.
+        /// A condition is only resolved once it is checked and found not to hold.
+        /// During maintenance new conditions are not notified, but recoveries still are.
+        fn update(
+            &mut self,
+            evaluation: Evaluation,
+            now: u64,
+            in_maintenance: bool,
+        ) -> Vec<Notification> {
+            let mut notifications = Vec::new();
+            let current: BTreeSet<String> =
+                evaluation.conditions.iter().map(|condition| condition.key.clone()).collect();
+            let holds = |key: &String| current.contains(key) || evaluation.is_unchecked(key);
+
+            let (network, label) = (&self.network, &self.label);
+            self.firing.retain(|key, summary| {
+                let still_firing = holds(key);
+                if !still_firing {
+                    notifications.push(Notification::new(network, label, "resolved", key, summary));
+                }
+                still_firing
+            });
+            self.pending.retain(|key, _| holds(key));
+
+            for condition in evaluation.conditions {
+                let since = *self.pending.entry(condition.key.clone()).or_insert(now);
+                let held = now.saturating_sub(since) >= condition.hold.as_secs();
+                if held && !in_maintenance && !self.firing.contains_key(&condition.key) {
+                    notifications.push(Notification::new(
+                        &self.network,
+                        &self.label,
+                        "firing",
+                        &condition.key,
+                        &condition.summary,
+                    ));
+                    self.firing.insert(condition.key, condition.summary);
+                }
+            }
+            notifications
+        }
+
+        /// A notification if the validator set differs from the last one seen. Changes during
+        /// maintenance are expected, so are notified afterwards if the set still differs.
+        fn validator_set_change(
+            &mut self,
+            validators: BTreeSet<String>,
+            in_maintenance: bool,
+        ) -> Option<Notification> {
+            if in_maintenance && self.validators.is_some() {
+                return None;
+            }
+            let previous = self.validators.replace(validators.clone())?;
+            if previous == validators {
+                return None;
+            }
+            let added: Vec<&str> = validators.difference(&previous).map(String::as_str).collect();
+            let removed: Vec<&str> = previous.difference(&validators).map(String::as_str).collect();
+            let mut summary = "Validator set changed".to_string();
+            if !added.is_empty() {
+                summary.push_str(&format!(", added {}", added.join(", ")));
+            }
+            if !removed.is_empty() {
+                summary.push_str(&format!(", removed {}", removed.join(", ")));
+            }
+            Some(Notification::new(&self.network, &self.label, "changed", "validator_set_changed", &summary))
+        }
+    }
+
+    /// Periodically evaluate the alerting rules for a network, posting notifications to the
+    /// configured webhooks. Runs forever, so should be spawned.
+    pub async fn run_alerts(config: AlertConfig, network: Network, announcements: AnnouncementStore) {
+        let client = match reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(error) => {
+                log::error!("Cannot create HTTP client for alerting: {}", error);
+                return;
+            }
+        };
+        let mut state = AlertState::new(&network.name, &network.label);
+        let mut interval = tokio::time::interval(config.evaluation_interval);
+        loop {
+            interval.tick().await;
+            let evaluation = check_conditions(&config, &network).await;
+            let in_maintenance = announcements.current_maintenance(&network.name).is_some();
+            let mut notifications = state.update(evaluation, unix_now(), in_maintenance);
+            if let Some(snapshot) = network.snapshots.get() {
+                let validators = snapshot
+                    .validators
+                    .iter()
+                    .map(|validator| validator.stash_account.to_string())
+                    .collect();
+                notifications.extend(state.validator_set_change(validators, in_maintenance));
+            }
+            for notification in notifications {
+                log::info!("Alert: {}", notification.text);
+                for webhook in &config.webhooks {
+                    if let Err(error) = send(&client, webhook, &notification).await {
+                        log::warn!("Failed to send alert to {}: {}", webhook, error);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Evaluate each rule against the chain connection, indexed state and probe results
+    async fn check_conditions(config: &AlertConfig, network: &Network) -> Evaluation {
+        let Network { chain, snapshots, probes, .. } = network;
+        let mut evaluation = Evaluation::default();
+        let conditions = &mut evaluation.conditions;
+
+        let status = chain.status();
+        if status.is_connecting() {
+            evaluation.unchecked.extend(["chain_unreachable".to_string(), "finality_stalled".to_string()]);
+        } else if !status.is_reachable() {
+            conditions.push(Condition {
+                key: "chain_unreachable".to_string(),
+                summary: format!("Chain endpoint {} is unreachable", chain.endpoint()),
+                hold: Duration::ZERO,
+            });
+            evaluation.unchecked.push("finality_stalled".to_string());
+        } else {
+            match chain.block_heights().await {
+                Some((best, finalized)) => {
+                    let lag = best.saturating_sub(finalized);
+                    if lag >= config.finality_lag_blocks {
+                        conditions.push(Condition {
+                            key: "finality_stalled".to_string(),
+                            summary: format!("Finality is {} blocks behind the best block #{}", lag, best),
+                            hold: Duration::ZERO,
+                        });
+                    }
+                }
+                None => evaluation.unchecked.push("finality_stalled".to_string()),
+            }
+        }
+
+        match snapshots.get() {
+            None => evaluation.unchecked.extend(["tss_down:".to_string(), "key_mismatch:".to_string()]),
+            Some(snapshot) => {
+                for validator in &snapshot.validators {
+                    let Some(probe) = probes.get(validator) else {
+                        evaluation.unchecked.push(format!("tss_down:{}", validator.stash_account));
+                        evaluation.unchecked.push(format!("key_mismatch:{}", validator.stash_account));
+                        continue;
+                    };
+                    if !probe.reachable {
+                        conditions.push(Condition {
+                            key: format!("tss_down:{}", validator.stash_account),
+                            summary: format!(
+                                "Threshold server {} of validator {} is not responding",
+                                validator.endpoint, validator.stash_account
+                            ),
+                            hold: config.tss_down_after,
+                        });
+                    }
+                    match probe.key_check {
+                        KeyCheck::Match => {}
+                        KeyCheck::Mismatch { .. } => conditions.push(Condition {
+                            key: format!("key_mismatch:{}", validator.stash_account),
+                            summary: format!(
+                                "Threshold server {} of validator {} does not hold its registered x25519 key",
+                                validator.endpoint, validator.stash_account
+                            ),
+                            hold: Duration::ZERO,
+                        }),
+                        KeyCheck::Unknown(_) => {
+                            evaluation.unchecked.push(format!("key_mismatch:{}", validator.stash_account))
+                        }
+                    }
+                }
+            }
+        }
+
+        evaluation
+    }
+
+    async fn send(client: &reqwest::Client, webhook: &str, notification: &Notification) -> anyhow::Result<()> {
+        client.post(webhook).json(notification).send().await?.error_for_status()?;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const TSS_DOWN_AFTER: Duration = Duration::from_secs(300);
+
+        fn state() -> AlertState {
+            AlertState::new("testnet", "Testnet")
+        }
+
+        fn tss_down() -> Condition {
+            Condition {
+                key: "tss_down:alice".to_string(),
+                summary: "Threshold server of alice is not responding".to_string(),
+                hold: TSS_DOWN_AFTER,
+            }
+        }
+
+        fn holding(conditions: Vec<Condition>) -> Evaluation {
+            Evaluation { conditions, unchecked: Vec::new() }
+        }
+
+        fn statuses(notifications: &[Notification]) -> Vec<(&str, &str)> {
+            notifications
+                .iter()
+                .map(|notification| (notification.status, notification.alert.as_str()))
+                .collect()
+        }
+
+        fn validators(stashes: &[&str]) -> BTreeSet<String> {
+            stashes.iter().map(|stash| stash.to_string()).collect()
+        }
+
+        #[test]
+        fn notifies_once_the_condition_has_held() {
+            let mut state = state();
+            assert!(state.update(holding(vec![tss_down()]), 1000, false).is_empty());
+            assert!(state.update(holding(vec![tss_down()]), 1299, false).is_empty());
+            let notifications = state.update(holding(vec![tss_down()]), 1300, false);
+            assert_eq!(statuses(&notifications), vec![("firing", "tss_down:alice")]);
+            assert_eq!(notifications[0].network, "testnet");
+            assert_eq!(notifications[0].text, "[FIRING] Testnet: Threshold server of alice is not responding");
+        }
+
+        #[test]
+        fn condition_clearing_before_hold_time_is_not_notified() {
+            let mut state = state();
+            state.update(holding(vec![tss_down()]), 1000, false);
+            assert!(state.update(holding(vec![]), 1100, false).is_empty());
+            // Starts again, so has to hold for the whole time again
+            assert!(state.update(holding(vec![tss_down()]), 1200, false).is_empty());
+            assert!(state.update(holding(vec![tss_down()]), 1400, false).is_empty());
+            assert_eq!(state.update(holding(vec![tss_down()]), 1500, false).len(), 1);
+        }
+
+        #[test]
+        fn notifies_each_problem_once() {
+            let mut state = state();
+            state.update(holding(vec![tss_down()]), 1000, false);
+            assert_eq!(state.update(holding(vec![tss_down()]), 1300, false).len(), 1);
+            assert!(state.update(holding(vec![tss_down()]), 1400, false).is_empty());
+            assert!(state.update(holding(vec![tss_down()]), 5000, false).is_empty());
+        }
+
+        #[test]
+        fn notifies_recovery() {
+            let mut state = state();
+            state.update(holding(vec![tss_down()]), 1000, false);
+            state.update(holding(vec![tss_down()]), 1300, false);
+            let notifications = state.update(holding(vec![]), 1400, false);
+            assert_eq!(statuses(&notifications), vec![("resolved", "tss_down:alice")]);
+            assert_eq!(notifications[0].text, "[RESOLVED] Testnet: Threshold server of alice is not responding");
+            assert!(state.update(holding(vec![]), 1500, false).is_empty());
+        }
+
+        #[test]
+        fn unchecked_conditions_are_not_resolved() {
+            let mut state = state();
+            state.update(holding(vec![tss_down()]), 1000, false);
+            state.update(holding(vec![tss_down()]), 1300, false);
+            let unchecked = |unchecked: &str| Evaluation {
+                conditions: Vec::new(),
+                unchecked: vec![unchecked.to_string()],
+            };
+            assert!(state.update(unchecked("tss_down:alice"), 1400, false).is_empty());
+            assert!(state.update(unchecked("tss_down:"), 1500, false).is_empty());
+            assert_eq!(
+                statuses(&state.update(unchecked("tss_down:bob"), 1600, false)),
+                vec![("resolved", "tss_down:alice")]
+            );
+        }
+
+        #[test]
+        fn unchecked_conditions_keep_their_hold_time() {
+            let mut state = state();
+            state.update(holding(vec![tss_down()]), 1000, false);
+            let unchecked = Evaluation {
+                conditions: Vec::new(),
+                unchecked: vec!["tss_down:".to_string()],
+            };
+            assert!(state.update(unchecked, 1100, false).is_empty());
+            assert_eq!(state.update(holding(vec![tss_down()]), 1300, false).len(), 1);
+        }
+
+        #[test]
+        fn suppresses_new_problems_during_maintenance() {
+            let mut state = state();
+            state.update(holding(vec![tss_down()]), 1000, true);
+            assert!(state.update(holding(vec![tss_down()]), 1300, true).is_empty());
+            // Still a problem once maintenance is over
+            let notifications = state.update(holding(vec![tss_down()]), 1400, false);
+            assert_eq!(statuses(&notifications), vec![("firing", "tss_down:alice")]);
+        }
+
+        #[test]
+        fn problems_fixed_during_maintenance_are_not_notified() {
+            let mut state = state();
+            state.update(holding(vec![tss_down()]), 1000, true);
+            state.update(holding(vec![tss_down()]), 1300, true);
+            assert!(state.update(holding(vec![]), 1400, true).is_empty());
+            assert!(state.update(holding(vec![]), 1500, false).is_empty());
+        }
+
+        #[test]
+        fn notifies_recovery_during_maintenance() {
+            let mut state = state();
+            state.update(holding(vec![tss_down()]), 1000, false);
+            state.update(holding(vec![tss_down()]), 1300, false);
+            let notifications = state.update(holding(vec![]), 1400, true);
+            assert_eq!(statuses(&notifications), vec![("resolved", "tss_down:alice")]);
+        }
+
+        #[test]
+        fn first_validator_set_is_not_a_change() {
+            let mut state = state();
+            assert!(state.validator_set_change(validators(&["alice", "bob"]), false).is_none());
+            assert!(state.validator_set_change(validators(&["alice", "bob"]), false).is_none());
+        }
+
+        #[test]
+        fn notifies_validator_set_change() {
+            let mut state = state();
+            state.validator_set_change(validators(&["alice", "bob"]), false);
+            let notification = state.validator_set_change(validators(&["alice", "charlie"]), false).unwrap();
+            assert_eq!(notification.status, "changed");
+            assert_eq!(notification.alert, "validator_set_changed");
+            assert_eq!(notification.summary, "Validator set changed, added charlie, removed bob");
+            assert!(state.validator_set_change(validators(&["alice", "charlie"]), false).is_none());
+        }
+
+        #[test]
+        fn holds_validator_set_change_during_maintenance() {
+            let mut state = state();
+            state.validator_set_change(validators(&["alice", "bob"]), false);
+            assert!(state.validator_set_change(validators(&["alice"]), true).is_none());
+            assert!(state.validator_set_change(validators(&["alice", "charlie"]), true).is_none());
+            let notification = state.validator_set_change(validators(&["alice", "charlie"]), false).unwrap();
+            assert_eq!(notification.summary, "Validator set changed, added charlie, removed bob");
+        }
+
+        #[test]
+        fn validator_set_restored_during_maintenance_is_not_a_change() {
+            let mut state = state();
+            state.validator_set_change(validators(&["alice", "bob"]), false);
+            assert!(state.validator_set_change(validators(&["alice"]), true).is_none());
+            assert!(state.validator_set_change(validators(&["alice", "bob"]), false).is_none());
+        }
+
+        #[tokio::test]
+        async fn posts_notification_to_webhook() {
+            use axum::{http::StatusCode, routing::post, Json, Router};
+            use tokio::sync::mpsc;
+
+            let (sender, mut received) = mpsc::channel(1);
+            let app = Router::new().route(
+                "/hook",
+                post(move |Json(body): Json<serde_json::Value>| async move {
+                    sender.send(body).await.unwrap();
+                    StatusCode::OK
+                }),
+            );
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+
+            let mut state = state();
+            let notification = state.validator_set_change(validators(&["alice"]), false);
+            assert!(notification.is_none());
+            let notification = state.validator_set_change(validators(&["bob"]), false).unwrap();
+            let client = reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build().unwrap();
+            send(&client, &format!("http://{}/hook", addr), &notification).await.unwrap();
+
+            let body = received.recv().await.unwrap();
+            assert_eq!(body["network"], "testnet");
+            assert_eq!(body["status"], "changed");
+            assert_eq!(body["alert"], "validator_set_changed");
+            assert_eq!(body["text"], "[CHANGED] Testnet: Validator set changed, added bob, removed alice");
+            assert_eq!(body["content"], body["text"]);
+        }
+
+        #[tokio::test]
+        async fn webhook_errors_are_reported() {
+            use axum::{http::StatusCode, routing::post, Router};
+
+            let app = Router::new().route("/hook", post(|| async { StatusCode::INTERNAL_SERVER_ERROR }));
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+
+            let notification = Notification::new("testnet", "Testnet", "firing", "chain_unreachable", "Unreachable");
+            let client = reqwest::Client::new();
+            assert!(send(&client, &format!("http://{}/hook", addr), &notification).await.is_err());
+        }
+    }
+}}
@@ -0,0 +1,462 @@
+use crate::display_timestamp;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnnouncementKind {
+    /// Something is broken, until marked resolved
+    Incident,
+    /// Planned work between `starts_at` and `ends_at`, during which outages are expected
+    Maintenance,
+    /// Anything else users should know about
+    Notice,
+}
+
+impl AnnouncementKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnouncementKind::Incident => "Incident",
+            AnnouncementKind::Maintenance => "Maintenance",
+            AnnouncementKind::Notice => "Notice",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "Incident" => Some(AnnouncementKind::Incident),
+            "Maintenance" => Some(AnnouncementKind::Maintenance),
+            "Notice" => Some(AnnouncementKind::Notice),
+            _ => None,
+        }
+    }
+}
+
+/// A message from the network operators
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Announcement {
+    pub id: i64,
+    pub kind: AnnouncementKind,
+    pub title: String,
+    pub body: String,
+    /// Unix time in seconds at which it was published
+    pub created_at: u64,
+    /// Unix time in seconds from which it applies, if not straight away
+    pub starts_at: Option<u64>,
+    /// Unix time in seconds until which it applies, if it expires
+    pub ends_at: Option<u64>,
+    pub resolved: bool,
+    /// The network it is about, or `None` for all of them
+    pub network: Option<String>,
+}
+
+impl Announcement {
+    /// Whether it should be shown for the given network
+    pub fn applies_to(&self, network: &str) -> bool {
+        self.network.as_deref().map_or(true, |name| name == network)
+    }
+
+    /// Whether it should currently be shown as a banner
+    pub fn is_active(&self, now: u64) -> bool {
+        !self.resolved
+            && self.starts_at.map_or(true, |starts_at| starts_at <= now)
+            && self.ends_at.map_or(true, |ends_at| now < ends_at)
+    }
+
+    /// Whether it is a maintenance window which is in progress
+    pub fn is_maintenance(&self, now: u64) -> bool {
+        self.kind == AnnouncementKind::Maintenance && self.is_active(now)
+    }
+}
+
+/// Most announcements shown in the timeline
+const TIMELINE_LENGTH: usize = 20;
+
+/// Announcements along with the server time at which they were read, so that which are active
+/// is decided the same way when rendering on the server and in the browser
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Announcements {
+    pub now: u64,
+    /// Newest first
+    pub announcements: Vec<Announcement>,
+}
+
+/// Announcements for the given network, including those for all networks
+#[server(GetAnnouncements, "/api")]
+pub async fn get_announcements(network: String) -> Result<Announcements, ServerFnError> {
+    let network = crate::network_state(&network)?;
+    Ok(Announcements {
+        now: unix_now(),
+        announcements: crate::announcement_store()?.list_for(&network.name),
+    })
+}
+
+/// Banners for the announcements which currently apply
+#[component]
+pub fn AnnouncementBanners(announcements: Announcements) -> impl IntoView {
+    let now = announcements.now;
+    announcements
+        .announcements
+        .into_iter()
+        .filter(|announcement| announcement.is_active(now))
+        .map(|announcement| {
+            let color = match announcement.kind {
+                AnnouncementKind::Incident => "red",
+                AnnouncementKind::Maintenance => "blue",
+                AnnouncementKind::Notice => "gray",
+            };
+            view! {
+                <div class=format!("my-4 p-4 rounded-xl bg-{}-500/20 text-{}-900", color, color)>
+                    <p class="font-bold">{announcement.title}</p>
+                    <p class="text-sm whitespace-pre-line">{announcement.body}</p>
+                    {announcement
+                        .ends_at
+                        .filter(|_| announcement.kind == AnnouncementKind::Maintenance)
+                        .map(|ends_at| {
+                            view! { <p class="text-sm">{format!("Until {}", display_timestamp(ends_at))}</p> }
+                        })}
+                </div>
+            }
+        })
+        .collect_view()
+}
+
+/// The most recent announcements, newest first
+#[component]
+pub fn AnnouncementTimeline(announcements: Announcements) -> impl IntoView {
+    let announcements = announcements.announcements;
+    if announcements.is_empty() {
+        return ().into_view();
+    }
+    view! {
+        <h2 class="my-4 block font-sans text-xl antialiased leading-snug tracking-normal mt-4 text-gray-700">
+            "Announcements"
+        </h2>
+        <ol class="p-4 border-l border-blue-gray-100 text-blue-gray-900 bg-white shadow-md rounded-xl bg-clip-border">
+            {announcements
+                .into_iter()
+                .take(TIMELINE_LENGTH)
+                .map(|announcement| {
+                    let when = match (announcement.starts_at, announcement.ends_at) {
+                        (Some(starts_at), Some(ends_at)) => {
+                            format!("{} to {}", display_timestamp(starts_at), display_timestamp(ends_at))
+                        }
+                        (Some(starts_at), None) => format!("From {}", display_timestamp(starts_at)),
+                        _ => display_timestamp(announcement.created_at),
+                    };
+                    view! {
+                        <li class="mb-4">
+                            <p class="text-xs text-gray-500">
+                                {format!(
+                                    "{} · {}{}",
+                                    announcement.kind.as_str(),
+                                    when,
+                                    if announcement.resolved { " · Resolved" } else { "" },
+                                )}
+                            </p>
+                            <p class="font-bold">{announcement.title}</p>
+                            <p class="text-sm whitespace-pre-line">{announcement.body}</p>
+                        </li>
+                    }
+                })
+                .collect_view()}
+        </ol>
+    }
+    .into_view()
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::{chain::unix_now, database::Database, state::AppState};
+        use axum::{
+            extract::{Path, State},
+            http::{header, HeaderMap, StatusCode},
+            routing::{get, put},
+            Json, Router,
+        };
+        use rusqlite::{params, Row};
+        use std::sync::{Arc, RwLock};
+
+        const SCHEMA: &str = "
+            CREATE TABLE IF NOT EXISTS announcements (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                title TEXT NOT NULL,
+                body TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                starts_at INTEGER,
+                ends_at INTEGER,
+                resolved INTEGER NOT NULL,
+                network TEXT
+            );
+        ";
+
+        /// An announcement as written through the admin API
+        #[derive(Deserialize)]
+        pub struct AnnouncementRequest {
+            pub kind: AnnouncementKind,
+            pub title: String,
+            #[serde(default)]
+            pub body: String,
+            pub starts_at: Option<u64>,
+            pub ends_at: Option<u64>,
+            #[serde(default)]
+            pub resolved: bool,
+            /// Name of the network it is about, or missing for all of them
+            #[serde(default)]
+            pub network: Option<String>,
+        }
+
+        impl AnnouncementRequest {
+            fn validate(&self, state: &AppState) -> Result<(), String> {
+                if let Some(network) = &self.network {
+                    if state.networks.get(network).is_none() {
+                        return Err(format!("Unknown network {}", network));
+                    }
+                }
+                if self.title.trim().is_empty() {
+                    return Err("An announcement needs a title".to_string());
+                }
+                if let (Some(starts_at), Some(ends_at)) = (self.starts_at, self.ends_at) {
+                    if ends_at <= starts_at {
+                        return Err("ends_at should be after starts_at".to_string());
+                    }
+                }
+                if self.kind == AnnouncementKind::Maintenance && (self.starts_at.is_none() || self.ends_at.is_none()) {
+                    return Err("A maintenance window needs both starts_at and ends_at".to_string());
+                }
+                Ok(())
+            }
+        }
+
+        /// Announcements kept in the local database, with a copy in memory so that they can be
+        /// checked without waiting on the database
+        #[derive(Clone)]
+        pub struct AnnouncementStore {
+            database: Database,
+            announcements: Arc<RwLock<Vec<Announcement>>>,
+        }
+
+        impl AnnouncementStore {
+            pub async fn open(database: Database) -> anyhow::Result<Self> {
+                database.create_tables(SCHEMA)?;
+                let announcements = database
+                    .with_connection(|connection| {
+                        connection
+                            .prepare("SELECT * FROM announcements ORDER BY id DESC")?
+                            .query_map([], read_announcement)?
+                            .collect::<rusqlite::Result<Vec<_>>>()
+                    })
+                    .await?;
+                Ok(AnnouncementStore {
+                    database,
+                    announcements: Arc::new(RwLock::new(announcements)),
+                })
+            }
+
+            /// All announcements, newest first
+            pub fn list(&self) -> Vec<Announcement> {
+                self.announcements.read().map(|announcements| announcements.clone()).unwrap_or_default()
+            }
+
+            /// Announcements for the given network, including those for all networks, newest first
+            pub fn list_for(&self, network: &str) -> Vec<Announcement> {
+                self.list().into_iter().filter(|announcement| announcement.applies_to(network)).collect()
+            }
+
+            /// The maintenance window in progress on the given network, if there is one
+            pub fn current_maintenance(&self, network: &str) -> Option<Announcement> {
+                let now = unix_now();
+                self.list_for(network).into_iter().find(|announcement| announcement.is_maintenance(now))
+            }
+
+            async fn create(&self, request: AnnouncementRequest) -> anyhow::Result<Announcement> {
+                let mut announcement = Announcement {
+                    id: 0,
+                    kind: request.kind,
+                    title: request.title,
+                    body: request.body,
+                    created_at: unix_now(),
+                    starts_at: request.starts_at,
+                    ends_at: request.ends_at,
+                    resolved: request.resolved,
+                    network: request.network,
+                };
+                let row = announcement.clone();
+                announcement.id = self
+                    .database
+                    .with_connection(move |connection| {
+                        connection.execute(
+                            "INSERT INTO announcements (kind, title, body, created_at, starts_at, ends_at, resolved, network)
+                            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                            params![
+                                row.kind.as_str(),
+                                row.title,
+                                row.body,
+                                row.created_at,
+                                row.starts_at,
+                                row.ends_at,
+                                row.resolved,
+                                row.network,
+                            ],
+                        )?;
+                        Ok(connection.last_insert_rowid())
+                    })
+                    .await?;
+                if let Ok(mut announcements) = self.announcements.write() {
+                    announcements.insert(0, announcement.clone());
+                }
+                Ok(announcement)
+            }
+
+            async fn update(&self, id: i64, request: AnnouncementRequest) -> anyhow::Result<Option<Announcement>> {
+                let Some(existing) = self.list().into_iter().find(|announcement| announcement.id == id) else {
+                    return Ok(None);
+                };
+                let announcement = Announcement {
+                    id,
+                    kind: request.kind,
+                    title: request.title,
+                    body: request.body,
+                    created_at: existing.created_at,
+                    starts_at: request.starts_at,
+                    ends_at: request.ends_at,
+                    resolved: request.resolved,
+                    network: request.network,
+                };
+                let row = announcement.clone();
+                self.database
+                    .with_connection(move |connection| {
+                        connection.execute(
+                            "UPDATE announcements SET kind = ?2, title = ?3, body = ?4, starts_at = ?5, ends_at = ?6,
+                                resolved = ?7, network = ?8
+                            WHERE id = ?1",
+                            params![
+                                row.id,
+                                row.kind.as_str(),
+                                row.title,
+                                row.body,
+                                row.starts_at,
+                                row.ends_at,
+                                row.resolved,
+                                row.network,
+                            ],
+                        )
+                    })
+                    .await?;
+                if let Ok(mut announcements) = self.announcements.write() {
+                    for existing in announcements.iter_mut().filter(|existing| existing.id == id) {
+                        *existing = announcement.clone();
+                    }
+                }
+                Ok(Some(announcement))
+            }
+
+            async fn delete(&self, id: i64) -> anyhow::Result<bool> {
+                let deleted = self
+                    .database
+                    .with_connection(move |connection| connection.execute("DELETE FROM announcements WHERE id = ?1", [id]))
+                    .await?;
+                if let Ok(mut announcements) = self.announcements.write() {
+                    announcements.retain(|announcement| announcement.id != id);
+                }
+                Ok(deleted > 0)
+            }
+        }
+
+        fn read_announcement(row: &Row) -> rusqlite::Result<Announcement> {
+            let kind: String = row.get("kind")?;
+            Ok(Announcement {
+                id: row.get("id")?,
+                kind: AnnouncementKind::parse(&kind).unwrap_or(AnnouncementKind::Notice),
+                title: row.get("title")?,
+                body: row.get("body")?,
+                created_at: row.get("created_at")?,
+                starts_at: row.get("starts_at")?,
+                ends_at: row.get("ends_at")?,
+                resolved: row.get("resolved")?,
+                network: row.get("network")?,
+            })
+        }
+
+        type AdminResult<T> = Result<T, (StatusCode, String)>;
+
+        /// Endpoints for operators to publish announcements, to be nested under `/admin`.
+        /// Requests need an `Authorization: Bearer <ADMIN_TOKEN>` header.
+        pub fn admin_routes() -> Router<AppState> {
+            Router::new()
+                .route("/announcements", get(list_announcements).post(create_announcement))
+                .route("/announcements/:id", put(update_announcement).delete(delete_announcement))
+        }
+
+        async fn list_announcements(State(state): State<AppState>, headers: HeaderMap) -> AdminResult<Json<Vec<Announcement>>> {
+            authorize(&state, &headers)?;
+            Ok(Json(state.announcements.list()))
+        }
+
+        async fn create_announcement(
+            State(state): State<AppState>,
+            headers: HeaderMap,
+            Json(request): Json<AnnouncementRequest>,
+        ) -> AdminResult<(StatusCode, Json<Announcement>)> {
+            authorize(&state, &headers)?;
+            request.validate(&state).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            let announcement = state.announcements.create(request).await.map_err(internal_error)?;
+            log::info!("Published announcement {}: {}", announcement.id, announcement.title);
+            Ok((StatusCode::CREATED, Json(announcement)))
+        }
+
+        async fn update_announcement(
+            State(state): State<AppState>,
+            headers: HeaderMap,
+            Path(id): Path<i64>,
+            Json(request): Json<AnnouncementRequest>,
+        ) -> AdminResult<Json<Announcement>> {
+            authorize(&state, &headers)?;
+            request.validate(&state).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            state
+                .announcements
+                .update(id, request)
+                .await
+                .map_err(internal_error)?
+                .map(Json)
+                .ok_or_else(|| (StatusCode::NOT_FOUND, "No announcement with this ID".to_string()))
+        }
+
+        async fn delete_announcement(
+            State(state): State<AppState>,
+            headers: HeaderMap,
+            Path(id): Path<i64>,
+        ) -> AdminResult<StatusCode> {
+            authorize(&state, &headers)?;
+            match state.announcements.delete(id).await.map_err(internal_error)? {
+                true => Ok(StatusCode::NO_CONTENT),
+                false => Err((StatusCode::NOT_FOUND, "No announcement with this ID".to_string())),
+            }
+        }
+
+        /// Check the request carries the admin token. Without a configured token the admin API
+        /// is disabled.
+        fn authorize(state: &AppState, headers: &HeaderMap) -> AdminResult<()> {
+            let Some(token) = state.admin_token.as_deref() else {
+                return Err((StatusCode::FORBIDDEN, "The admin API is disabled as no ADMIN_TOKEN is set".to_string()));
+            };
+            let given = headers
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .unwrap_or_default();
+            // Compare every byte so that the time taken does not reveal how much matched
+            let matches = given.len() == token.len()
+                && given.bytes().zip(token.bytes()).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0;
+            if matches {
+                Ok(())
+            } else {
+                Err((StatusCode::UNAUTHORIZED, "Missing or incorrect admin token".to_string()))
+            }
+        }
+
+        fn internal_error(error: impl ToString) -> (StatusCode, String) {
+            (StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    use crate::{
+        chain::ChainStatus,
+        chain_info::{query_chain_info, ChainInfo},
+        diff::{ChangeKind, EntryChange, FieldChange, StateDiff},
+        indexer::{parse_hash, BlockRef, Snapshot, UnknownBlock},
+        network::{NetworkQuery, NetworkSummary},
+        probe::KeyCheck,
+        program::Program,
+        registered_account::RegisteredAccount,
+        state::AppState,
+        validator::Validator,
+    };
+    use axum::{
+        extract::{Query, State},
+        http::StatusCode,
+        response::Html,
+        routing::get,
+        Json, Router,
+    };
+    use serde::{Deserialize, Serialize};
+    use std::sync::Arc;
+    use utoipa::{IntoParams, OpenApi, ToSchema};
+
+    /// Page size used when no limit is given
+    const DEFAULT_LIMIT: usize = 100;
+    /// Largest page size a client may ask for
+    const MAX_LIMIT: usize = 1000;
+
+    /// Route serving the OpenAPI document describing the JSON endpoints
+    pub const OPENAPI_ROUTE: &str = "/openapi.json";
+    /// Route serving a page for browsing the OpenAPI document
+    pub const API_DOCS_ROUTE: &str = "/api-docs";
+
+    type ApiResult<T> = Result<Json<T>, (StatusCode, String)>;
+
+    #[derive(OpenApi)]
+    #[openapi(
+        info(title = "Entropy Network Status API"),
+        paths(networks, accounts, programs, validators, chain, diff),
+        components(schemas(
+            NetworkSummary,
+            AccountList,
+            ProgramList,
+            ValidatorList,
+            ChainResponse,
+            BlockRef,
+            RegisteredAccount,
+            Program,
+            Validator,
+            crate::probe::ProbeResult,
+            KeyCheck,
+            ChainStatus,
+            ChainInfo,
+            StateDiff,
+            EntryChange,
+            FieldChange,
+            ChangeKind,
+            crate::HexVec,
+        ))
+    )]
+    struct ApiDoc;
+
+    /// Versioned JSON endpoints for scripts and bots, to be nested under `/v1`
+    pub fn routes() -> Router<AppState> {
+        Router::new()
+            .route("/networks", get(networks))
+            .route("/accounts", get(accounts))
+            .route("/programs", get(programs))
+            .route("/validators", get(validators))
+            .route("/chain", get(chain))
+            .route("/diff", get(diff))
+    }
+
+    /// A page of results, along with the block they were read at
+    #[derive(Serialize, ToSchema)]
+    #[aliases(
+        AccountList = Page<RegisteredAccount>,
+        ProgramList = Page<Program>,
+        ValidatorList = Page<Validator>
+    )]
+    pub struct Page<T> {
+        pub block: BlockRef,
+        /// Number of results matching the filters, before pagination
+        pub total: usize,
+        pub offset: usize,
+        pub limit: usize,
+        pub items: Vec<T>,
+    }
+
+    #[derive(Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct AccountFilter {
+        /// Name of the network, defaulting to the first configured
+        pub network: Option<String>,
+        /// `public`, `permissioned` or `private`
+        pub key_visibility: Option<String>,
+        /// Only accounts using the program with this hash
+        pub program: Option<String>,
+        pub offset: Option<usize>,
+        pub limit: Option<usize>,
+    }
+
+    #[derive(Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct ProgramFilter {
+        /// Name of the network, defaulting to the first configured
+        pub network: Option<String>,
+        /// Only programs deployed by this account
+        pub deployer: Option<String>,
+        pub configurable: Option<bool>,
+        pub offset: Option<usize>,
+        pub limit: Option<usize>,
+    }
+
+    #[derive(Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct ValidatorFilter {
+        /// Name of the network, defaulting to the first configured
+        pub network: Option<String>,
+        /// `up`, `down` or `unknown`
+        pub status: Option<String>,
+        /// `match`, `mismatch` or `unknown`
+        pub key_check: Option<String>,
+        pub offset: Option<usize>,
+        pub limit: Option<usize>,
+    }
+
+    #[derive(Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct DiffQuery {
+        /// Name of the network, defaulting to the first configured
+        pub network: Option<String>,
+        /// Block number or `0x` prefixed hash to compare from
+        pub from: String,
+        /// Block number or `0x` prefixed hash to compare to, defaulting to the latest indexed block
+        pub to: Option<String>,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    pub struct ChainResponse {
+        pub endpoint: String,
+        pub status: ChainStatus,
+        /// Missing when the chain cannot be reached
+        pub info: Option<ChainInfo>,
+    }
+
+    /// The monitored networks, the first of which is used when no network is given
+    #[utoipa::path(get, path = "/v1/networks", responses((status = 200, body = Vec<NetworkSummary>)))]
+    async fn networks(State(state): State<AppState>) -> ApiResult<Vec<NetworkSummary>> {
+        Ok(Json(state.networks.summaries()))
+    }
+
+    /// Registered accounts
+    #[utoipa::path(
+        get,
+        path = "/v1/accounts",
+        params(AccountFilter),
+        responses(
+            (status = 200, body = AccountList),
+            (status = 400, description = "Invalid program hash"),
+            (status = 404, description = "Unknown network"),
+            (status = 503, description = "No finalized block has been indexed yet"),
+        )
+    )]
+    async fn accounts(
+        State(state): State<AppState>,
+        Query(filter): Query<AccountFilter>,
+    ) -> ApiResult<Page<RegisteredAccount>> {
+        let snapshot = latest_snapshot(&state, filter.network.as_deref())?;
+        // Program pointers are stored in the same form as the hash is formatted here
+        let program = filter
+            .program
+            .as_deref()
+            .map(parse_hash)
+            .transpose()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid program hash: {}", e)))?
+            .map(|hash| format!("{:?}", hash));
+        let matching = snapshot
+            .accounts
+            .iter()
+            .filter(|account| {
+                filter
+                    .key_visibility
+                    .as_ref()
+                    .map_or(true, |visibility| account.key_visibility.0.eq_ignore_ascii_case(visibility))
+            })
+            .filter(|account| {
+                program
+                    .as_ref()
+                    .map_or(true, |program| account.program_pointers.contains(program))
+            });
+        Ok(Json(paginate(&snapshot, matching, filter.offset, filter.limit)))
+    }
+
+    /// Stored programs
+    #[utoipa::path(
+        get,
+        path = "/v1/programs",
+        params(ProgramFilter),
+        responses(
+            (status = 200, body = ProgramList),
+            (status = 404, description = "Unknown network"),
+            (status = 503, description = "No finalized block has been indexed yet"),
+        )
+    )]
+    async fn programs(
+        State(state): State<AppState>,
+        Query(filter): Query<ProgramFilter>,
+    ) -> ApiResult<Page<Program>> {
+        let snapshot = latest_snapshot(&state, filter.network.as_deref())?;
+        let matching = snapshot
+            .programs
+            .iter()
+            .filter(|program| filter.deployer.as_ref().map_or(true, |deployer| &program.deployer == deployer))
+            .filter(|program| {
+                filter
+                    .configurable
+                    .map_or(true, |configurable| program.configurable == configurable)
+            });
+        Ok(Json(paginate(&snapshot, matching, filter.offset, filter.limit)))
+    }
+
+    /// Validators and the latest check of their threshold servers
+    #[utoipa::path(
+        get,
+        path = "/v1/validators",
+        params(ValidatorFilter),
+        responses(
+            (status = 200, body = ValidatorList),
+            (status = 404, description = "Unknown network"),
+            (status = 503, description = "No finalized block has been indexed yet"),
+        )
+    )]
+    async fn validators(
+        State(state): State<AppState>,
+        Query(filter): Query<ValidatorFilter>,
+    ) -> ApiResult<Page<Validator>> {
+        let snapshot = latest_snapshot(&state, filter.network.as_deref())?;
+        let matching = snapshot
+            .validators
+            .iter()
+            .filter(|validator| {
+                filter.status.as_ref().map_or(true, |status| {
+                    let actual = match &validator.probe {
+                        Some(probe) if probe.reachable => "up",
+                        Some(_) => "down",
+                        None => "unknown",
+                    };
+                    actual.eq_ignore_ascii_case(status)
+                })
+            })
+            .filter(|validator| {
+                filter.key_check.as_ref().map_or(true, |key_check| {
+                    let actual = match validator.probe.as_ref().map(|probe| &probe.key_check) {
+                        Some(KeyCheck::Match) => "match",
+                        Some(KeyCheck::Mismatch { .. }) => "mismatch",
+                        Some(KeyCheck::Unknown(_)) | None => "unknown",
+                    };
+                    actual.eq_ignore_ascii_case(key_check)
+                })
+            });
+        Ok(Json(paginate(&snapshot, matching, filter.offset, filter.limit)))
+    }
+
+    /// Connection status and general information about the chain
+    #[utoipa::path(
+        get,
+        path = "/v1/chain",
+        params(NetworkQuery),
+        responses(
+            (status = 200, body = ChainResponse),
+            (status = 404, description = "Unknown network"),
+        )
+    )]
+    async fn chain(State(state): State<AppState>, Query(query): Query<NetworkQuery>) -> ApiResult<ChainResponse> {
+        let chain = &state.networks.resolve(query.network.as_deref())?.chain;
+        let info = match chain.get().await {
+            Ok((api, rpc)) => query_chain_info(&api, &rpc).await.ok(),
+            Err(_) => None,
+        };
+        Ok(Json(ChainResponse {
+            endpoint: chain.endpoint().to_string(),
+            status: chain.status(),
+            info,
+        }))
+    }
+
+    /// Accounts, programs and validators which were added, removed or modified between two blocks
+    #[utoipa::path(
+        get,
+        path = "/v1/diff",
+        params(DiffQuery),
+        responses(
+            (status = 200, body = StateDiff),
+            (status = 400, description = "A block could not be found"),
+            (status = 404, description = "Unknown network"),
+            (status = 503, description = "The chain cannot be reached, or no finalized block has been indexed yet"),
+        )
+    )]
+    async fn diff(State(state): State<AppState>, Query(query): Query<DiffQuery>) -> ApiResult<StateDiff> {
+        let network = state.networks.resolve(query.network.as_deref())?;
+        let from = network.snapshot_at(Some(&query.from)).await.map_err(snapshot_error)?;
+        let to = network.snapshot_at(query.to.as_deref()).await.map_err(snapshot_error)?;
+        Ok(Json(StateDiff::between(&from, &to)))
+    }
+
+    /// The OpenAPI document for the `/v1` endpoints, for generating clients
+    pub async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+        Json(ApiDoc::openapi())
+    }
+
+    /// A Swagger UI page showing the OpenAPI document
+    pub async fn api_docs() -> Html<String> {
+        Html(format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>Entropy Network Status API</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+    <script>SwaggerUIBundle({{ url: "{}", dom_id: "#swagger-ui" }});</script>
+</body>
+</html>"#,
+            OPENAPI_ROUTE
+        ))
+    }
+
+    fn latest_snapshot(state: &AppState, network: Option<&str>) -> Result<Arc<Snapshot>, (StatusCode, String)> {
+        state.networks.resolve(network)?.latest_snapshot().map_err(snapshot_error)
+    }
+
+    /// A block which does not exist is a bad request, otherwise the chain or indexer is unavailable
+    fn snapshot_error(error: anyhow::Error) -> (StatusCode, String) {
+        if error.is::<UnknownBlock>() {
+            (StatusCode::BAD_REQUEST, error.to_string())
+        } else {
+            (StatusCode::SERVICE_UNAVAILABLE, error.to_string())
+        }
+    }
+
+    fn paginate<'a, T: Clone + 'a>(
+        snapshot: &Snapshot,
+        matching: impl Iterator<Item = &'a T>,
+        offset: Option<usize>,
+        limit: Option<usize>,
+    ) -> Page<T> {
+        let matching: Vec<&T> = matching.collect();
+        let offset = offset.unwrap_or(0);
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+        Page {
+            block: snapshot.block.clone(),
+            total: matching.len(),
+            offset,
+            limit,
+            items: matching.into_iter().skip(offset).take(limit).cloned().collect(),
+        }
+    }
+}}
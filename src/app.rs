@@ -1,10 +1,23 @@
 use crate::{
+    account::AccountPage,
+    announcement::{get_announcements, AnnouncementBanners, AnnouncementTimeline},
+    blocks::{BlockPage, BlocksPage},
+    chain::{get_chain_status, ChainStatusBanner},
+    chain_info::{get_chain_info, ChainOverview},
+    diff::DiffPage,
     error_template::{AppError, ErrorTemplate},
     get_chain_endpoint,
-    program::{get_stored_programs, Program},
-    registered_account::{get_registered_accounts, RegisteredAccount},
-    validator::{get_validators, Validator},
-    DetailsTable,
+    history::HistoryCharts,
+    indexer::{get_snapshot, use_block, BlockPicker},
+    live::LiveIndicator,
+    network::{network_href, use_network, NetworkLayout},
+    program::{Program, ProgramPage},
+    registered_account::RegisteredAccount,
+    registration::{PendingRegistration, RegistrationOutcome},
+    signing::SigningPage,
+    uptime::{get_overall_status, HistoryPage, StatusHeadline},
+    validator::{Validator, ValidatorPage},
+    DetailsTable, UnavailableRow,
 };
 use leptos::*;
 use leptos_meta::*;
@@ -18,7 +31,7 @@ pub fn App() -> impl IntoView {
     view! {
         <Stylesheet id="leptos" href="/pkg/entropy-network-status-page.css"/>
 
-        <Title text="Entropy Testnet Status Page"/>
+        <Title text="Entropy Network Status Page"/>
 
         <Router fallback=|| {
             let mut outside_errors = Errors::default();
@@ -27,7 +40,17 @@ pub fn App() -> impl IntoView {
         }>
             <main>
                 <Routes>
-                    <Route path="" view=HomePage/>
+                    <Route path="/:network" view=NetworkLayout>
+                        <Route path="" view=HomePage/>
+                        <Route path="account/:id" view=AccountPage/>
+                        <Route path="program/:hash" view=ProgramPage/>
+                        <Route path="validator/:stash" view=ValidatorPage/>
+                        <Route path="history" view=HistoryPage/>
+                        <Route path="diff" view=DiffPage/>
+                        <Route path="blocks" view=BlocksPage/>
+                        <Route path="block/:block" view=BlockPage/>
+                        <Route path="signing" view=SigningPage/>
+                    </Route>
                 </Routes>
             </main>
         </Router>
@@ -36,14 +59,87 @@ pub fn App() -> impl IntoView {
 
 #[component]
 fn HomePage() -> impl IntoView {
-    let accounts = create_resource(|| (), move |_| get_registered_accounts());
-    let programs = create_resource(|| (), move |_| get_stored_programs());
-    let validators = create_resource(|| (), move |_| get_validators());
-    let endpoint = create_resource(|| (), move |_| get_chain_endpoint());
+    let network = use_network();
+    let block = use_block();
+    let snapshot = create_resource(
+        move || (network.get(), block.get()),
+        |(network, block)| get_snapshot(network, block),
+    );
+    let endpoint = create_resource(move || network.get(), get_chain_endpoint);
+    let status = create_resource(move || network.get(), get_chain_status);
+    // Refreshed whenever a new snapshot arrives
+    let latest_block = move || snapshot.get().and_then(|snapshot| snapshot.ok()).map(|snapshot| snapshot.block.number);
+    let chain_info = create_resource(
+        move || (network.get(), latest_block()),
+        |(network, _)| get_chain_info(network),
+    );
+    let overall_status = create_resource(
+        move || (network.get(), latest_block()),
+        |(network, _)| get_overall_status(network),
+    );
+    let announcements = create_resource(
+        move || (network.get(), latest_block()),
+        |(network, _)| get_announcements(network),
+    );
+    let (live, _set_live) = create_signal(false);
+    #[cfg(feature = "hydrate")]
+    crate::live::subscribe_to_snapshots(network, block, snapshot, _set_live);
     let loading = move || view! { <p>"Loading..."</p> };
     view! {
         <div class="container mx-auto">
-            <h1 class="text-2xl my-4">"Entropy Testnet Status Page"</h1>
+            <div class="flex items-center justify-between">
+                <h1 class="text-2xl my-4">"Entropy Network Status Page"</h1>
+                {move || {
+                    if block.get().is_some() {
+                        view! {
+                            <A href=network_href("") class="text-sm underline">
+                                "Back to latest"
+                            </A>
+                        }
+                            .into_view()
+                    } else {
+                        view! { <LiveIndicator live/> }.into_view()
+                    }
+                }}
+
+            </div>
+            <div class="flex items-center gap-4">
+                <BlockPicker/>
+                <A href=network_href("/diff") class="text-sm underline">
+                    "Compare two blocks"
+                </A>
+                <A href=network_href("/blocks") class="text-sm underline">
+                    "Recent blocks"
+                </A>
+                <A href=network_href("/signing") class="text-sm underline">
+                    "Signing activity"
+                </A>
+            </div>
+            <LiveLabel block/>
+            <Transition fallback=|| ()>
+                {move || {
+                    overall_status
+                        .get()
+                        .and_then(|status| status.ok())
+                        .map(|status| view! { <StatusHeadline status/> })
+                }}
+                {move || {
+                    announcements
+                        .get()
+                        .and_then(|announcements| announcements.ok())
+                        .map(|announcements| view! { <AnnouncementBanners announcements/> })
+                }}
+
+            </Transition>
+            <Transition fallback=|| ()>
+                {move || {
+                    status
+                        .get()
+                        .and_then(|status| status.ok())
+                        .map(|status| view! { <ChainStatusBanner status/> })
+                }}
+
+            </Transition>
             <Transition fallback=loading>
                 {move || {
                     endpoint
@@ -64,21 +160,63 @@ fn HomePage() -> impl IntoView {
                         })
                         .unwrap_or_default()
                 }}
+                {move || {
+                    snapshot
+                        .get()
+                        .and_then(|snapshot| snapshot.ok())
+                        .map(|snapshot| {
+                            view! {
+                                <p class="text-sm text-blue-gray-900" title=snapshot.block.hash>
+                                    {if block.get().is_some() {
+                                        format!(
+                                            "As of block #{} - threshold server checks are only known for the latest block",
+                                            snapshot.block.number,
+                                        )
+                                    } else {
+                                        format!("As of finalized block #{}", snapshot.block.number)
+                                    }}
+
+                                </p>
+                            }
+                                .into_view()
+                        })
+                        .unwrap_or_default()
+                }}
+
+            </Transition>
+            <LiveLabel block/>
+            <Transition fallback=loading>
+                {move || {
+                    chain_info
+                        .get()
+                        .map(move |info| match info {
+                            // Connection problems are already explained by the status banner
+                            Err(_) => ().into_view(),
+                            Ok(info) => view! { <ChainOverview info/> }.into_view(),
+                        })
+                        .unwrap_or_default()
+                }}
+
+            </Transition>
+            <LiveLabel block/>
+            <HistoryCharts/>
+            <Transition fallback=|| ()>
+                {move || {
+                    announcements
+                        .get()
+                        .and_then(|announcements| announcements.ok())
+                        .map(|announcements| view! { <AnnouncementTimeline announcements/> })
+                }}
 
             </Transition>
             <Transition fallback=loading>
                 {move || {
                     let existing_accounts = {
                         move || {
-                            accounts
+                            snapshot
                                 .get()
-                                .map(move |accounts| match accounts {
-                                    Err(e) => {
-                                        view! {
-                                            <pre class="error">"server error: " {e.to_string()}</pre>
-                                        }
-                                            .into_view()
-                                    }
+                                .map(move |snapshot| match snapshot.map(|snapshot| snapshot.accounts) {
+                                    Err(error) => view! { <UnavailableRow error/> }.into_view(),
                                     Ok(accounts) => {
                                         if accounts.is_empty() {
                                             view! {
@@ -121,17 +259,82 @@ fn HomePage() -> impl IntoView {
             </Transition>
             <Transition fallback=loading>
                 {move || {
-                    let stored_programs = {
+                    let pending_registrations = {
                         move || {
-                            programs
+                            snapshot
                                 .get()
-                                .map(move |programs| match programs {
-                                    Err(e) => {
-                                        view! {
-                                            <pre class="error">"server error: " {e.to_string()}</pre>
+                                .map(move |snapshot| match snapshot.map(|snapshot| snapshot.pending_registrations) {
+                                    Err(error) => view! { <UnavailableRow error/> }.into_view(),
+                                    Ok(registrations) => {
+                                        if registrations.is_empty() {
+                                            view! {
+                                                <tr>
+                                                    <td>"No pending registrations."</td>
+                                                </tr>
+                                            }
+                                                .into_view()
+                                        } else {
+                                            registrations
+                                                .into_iter()
+                                                .map(move |registration| {
+                                                    view! { <PendingRegistration registration/> }
+                                                })
+                                                .collect_view()
                                         }
-                                            .into_view()
                                     }
+                                })
+                                .unwrap_or_default()
+                        }
+                    };
+                    view! {
+                        <DetailsTable
+                            title="Pending Registrations"
+                            headings=vec![
+                                "Account ID",
+                                "Access Mode",
+                                "Confirmations",
+                                "Since Block",
+                                "Waiting",
+                            ]
+                        >
+
+                            {pending_registrations}
+                        </DetailsTable>
+                    }
+                }}
+
+            </Transition>
+            <Transition fallback=|| ()>
+                {move || {
+                    snapshot
+                        .get()
+                        .and_then(|snapshot| snapshot.ok())
+                        .map(|snapshot| snapshot.recent_registrations)
+                        .filter(|outcomes| !outcomes.is_empty())
+                        .map(|outcomes| {
+                            view! {
+                                <DetailsTable
+                                    title="Recently Ended Registrations"
+                                    headings=vec!["Account ID", "Result", "Block", "Took"]
+                                >
+                                    {outcomes
+                                        .into_iter()
+                                        .map(|outcome| view! { <RegistrationOutcome outcome/> })
+                                        .collect_view()}
+                                </DetailsTable>
+                            }
+                        })
+                }}
+
+            </Transition>
+            <Transition fallback=loading>
+                {move || {
+                    let stored_programs = {
+                        move || {
+                            snapshot
+                                .get()
+                                .map(move |snapshot| match snapshot.map(|snapshot| snapshot.programs) {
+                                    Err(error) => view! { <UnavailableRow error/> }.into_view(),
                                     Ok(programs) => {
                                         if programs.is_empty() {
                                             view! {
@@ -178,15 +381,10 @@ fn HomePage() -> impl IntoView {
                 {move || {
                     let current_validators = {
                         move || {
-                            validators
+                            snapshot
                                 .get()
-                                .map(move |validators| match validators {
-                                    Err(e) => {
-                                        view! {
-                                            <pre class="error">"server error: " {e.to_string()}</pre>
-                                        }
-                                            .into_view()
-                                    }
+                                .map(move |snapshot| match snapshot.map(|snapshot| snapshot.validators) {
+                                    Err(error) => view! { <UnavailableRow error/> }.into_view(),
                                     Ok(validators) => {
                                         if validators.is_empty() {
                                             view! {
@@ -211,7 +409,16 @@ fn HomePage() -> impl IntoView {
                     view! {
                         <DetailsTable
                             title="Validators"
-                            headings=vec!["TSS Account ID", "X25519 Public Key", "Socket Address"]
+                            headings=vec![
+                                "Stash Account ID",
+                                "TSS Account ID",
+                                "X25519 Public Key",
+                                "Key Check",
+                                "Socket Address",
+                                "Status",
+                                "Latency",
+                                "Last Seen",
+                            ]
                         >
                             {current_validators}
                         </DetailsTable>
@@ -222,3 +429,17 @@ fn HomePage() -> impl IntoView {
         </div>
     }
 }
+
+/// Marks a panel which always shows the present, when an earlier block is chosen
+#[component]
+fn LiveLabel(block: Memo<Option<String>>) -> impl IntoView {
+    move || {
+        block.get().map(|_| {
+            view! {
+                <p class="mt-4 text-xs font-bold uppercase text-amber-900">
+                    "Live - not as of the chosen block"
+                </p>
+            }
+        })
+    }
+}
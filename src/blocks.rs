@@ -0,0 +1,618 @@
+use crate::{
+    display_hash, display_timestamp,
+    network::{network_href, use_network},
+    DetailRow, DetailsList, DetailsTable, DisplayValue, LinkedValue,
+};
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+/// How many blocks are listed on the blocks page
+pub const RECENT_BLOCKS: u32 = 20;
+
+/// Most blocks read back from the best block when looking for ones with activity in a pallet
+pub const MAX_SCANNED_BLOCKS: u32 = 500;
+
+/// The Entropy pallets blocks can be filtered by, with the name they are shown with
+pub const ENTROPY_PALLETS: [(&str, &str); 3] = [
+    ("Relayer", "Relayer"),
+    ("Programs", "Programs"),
+    ("StakingExtension", "Staking Extension"),
+];
+
+/// A block as listed on the blocks page
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockSummary {
+    pub number: u32,
+    pub hash: String,
+    /// The validator which produced the block, if it could be worked out from the digest
+    pub author: Option<String>,
+    pub extrinsic_count: usize,
+    /// Unix time in milliseconds, from the timestamp inherent
+    pub timestamp: Option<u64>,
+    pub finalized: bool,
+    /// Pallets with calls or events in this block
+    pub pallets: Vec<String>,
+}
+
+/// A decoded extrinsic
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtrinsicSummary {
+    pub index: u32,
+    pub pallet: String,
+    pub call: String,
+    /// Missing for inherents
+    pub signer: Option<String>,
+    /// Whether it succeeded, from the `System` event it emitted
+    pub success: Option<bool>,
+    /// The call arguments, shortened if very long
+    pub arguments: String,
+}
+
+/// A decoded event
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventSummary {
+    pub index: u32,
+    /// The extrinsic which emitted the event, if any
+    pub extrinsic: Option<u32>,
+    pub pallet: String,
+    pub variant: String,
+    /// The event fields, shortened if very long
+    pub fields: String,
+}
+
+/// Everything in a block
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockDetails {
+    pub block: BlockSummary,
+    pub parent_hash: String,
+    pub extrinsics: Vec<ExtrinsicSummary>,
+    pub events: Vec<EventSummary>,
+}
+
+/// The latest `count` blocks, newest first. Given one of the `ENTROPY_PALLETS`, the latest
+/// `count` blocks with activity in it, out of the last `MAX_SCANNED_BLOCKS`.
+#[server(GetRecentBlocks, "/api")]
+pub async fn get_recent_blocks(
+    network: String,
+    count: u32,
+    pallet: Option<String>,
+) -> Result<Vec<BlockSummary>, ServerFnError> {
+    if let Some(pallet) = &pallet {
+        if !ENTROPY_PALLETS.iter().any(|(name, _)| name == pallet) {
+            return Err(ServerFnError::Args(format!("Blocks cannot be filtered by pallet {}", pallet)));
+        }
+    }
+    let (api, rpc) = crate::get_api_rpc(&network).await?;
+    query_recent_blocks(&api, &rpc, count.min(MAX_RECENT_BLOCKS), pallet.as_deref())
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// The decoded extrinsics and events of the block with the given number or hash
+#[server(GetBlockDetails, "/api")]
+pub async fn get_block_details(network: String, block: String) -> Result<BlockDetails, ServerFnError> {
+    let block = crate::block_ref(&network, Some(block)).await?;
+    let block_hash = block
+        .block_hash()
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    let (api, rpc) = crate::get_api_rpc(&network).await?;
+    query_block_details(&api, &rpc, block_hash)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// The pallet chosen with the `?pallet=` query parameter, if any
+fn use_pallet_filter() -> Memo<Option<String>> {
+    let query = use_query_map();
+    create_memo(move |_| query.with(|query| query.get("pallet").cloned().filter(|pallet| !pallet.is_empty())))
+}
+
+/// Links for showing only activity in one of the Entropy pallets
+#[component]
+fn PalletFilter(
+    /// Path of the page within the network
+    path: String,
+    pallet: Memo<Option<String>>,
+) -> impl IntoView {
+    let link_class = move |name: Option<&'static str>| {
+        move || {
+            if pallet.get().as_deref() == name {
+                "px-2 py-1 rounded-md bg-blue-100 font-semibold"
+            } else {
+                "px-2 py-1 rounded-md bg-blue-50"
+            }
+        }
+    };
+    view! {
+        <div class="flex items-center gap-2 my-4 text-sm text-blue-gray-900">
+            "Show "
+            <A href=network_href(&path) class=link_class(None)>
+                "All pallets"
+            </A>
+            {ENTROPY_PALLETS
+                .into_iter()
+                .map(|(name, label)| {
+                    view! {
+                        <A href=network_href(&format!("{}?pallet={}", path, name)) class=link_class(Some(name))>
+                            {label}
+                        </A>
+                    }
+                })
+                .collect_view()}
+        </div>
+    }
+}
+
+/// Lists the most recent blocks
+#[component]
+pub fn BlocksPage() -> impl IntoView {
+    let network = use_network();
+    let pallet = use_pallet_filter();
+    let blocks = create_resource(
+        move || (network.get(), pallet.get()),
+        |(network, pallet)| get_recent_blocks(network, RECENT_BLOCKS, pallet),
+    );
+    view! {
+        <div class="container mx-auto">
+            <A href=network_href("") class="text-sm underline">
+                "Back to status page"
+            </A>
+            <div class="flex items-center justify-between">
+                <h1 class="text-2xl my-4">"Recent Blocks"</h1>
+                <button class="text-sm px-2 py-1 rounded-md bg-blue-50" on:click=move |_| blocks.refetch()>
+                    "Refresh"
+                </button>
+            </div>
+            <PalletFilter path="/blocks".to_string() pallet/>
+            <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                {move || {
+                    blocks
+                        .get()
+                        .map(|blocks| match blocks {
+                            Err(e) => {
+                                view! { <pre class="error">"server error: " {e.to_string()}</pre> }
+                                    .into_view()
+                            }
+                            Ok(blocks) => {
+                                let rows = if blocks.is_empty() {
+                                    view! {
+                                        <tr>
+                                            <td class="p-4">
+                                                {match pallet.get() {
+                                                    Some(pallet) => {
+                                                        format!(
+                                                            "No {} activity in the last {} blocks.",
+                                                            pallet,
+                                                            MAX_SCANNED_BLOCKS,
+                                                        )
+                                                    }
+                                                    None => "No blocks.".to_string(),
+                                                }}
+                                            </td>
+                                        </tr>
+                                    }
+                                        .into_view()
+                                } else {
+                                    blocks.into_iter().map(|block| view! { <BlockRow block/> }).collect_view()
+                                };
+                                view! {
+                                    <DetailsTable
+                                        title="Blocks"
+                                        headings=vec![
+                                            "Number",
+                                            "Hash",
+                                            "Author",
+                                            "Extrinsics",
+                                            "Time",
+                                            "Finalized?",
+                                        ]
+                                    >
+                                        {rows}
+                                    </DetailsTable>
+                                }
+                                    .into_view()
+                            }
+                        })
+                        .unwrap_or_default()
+                }}
+
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+fn BlockRow(block: BlockSummary) -> impl IntoView {
+    view! {
+        <tr class="hover:bg-gray-200">
+            <LinkedValue value=format!("#{}", block.number) href=network_href(&format!("/block/{}", block.number))/>
+            <DisplayValue value=display_hash(&block.hash) long_value=Some(block.hash)/>
+            <DisplayValue value=block.author.unwrap_or_default() long_value=None/>
+            <DisplayValue value=block.extrinsic_count.to_string() long_value=None/>
+            <DisplayValue value=block.timestamp.map(|ms| display_timestamp(ms / 1000)).unwrap_or_default() long_value=None/>
+            <DisplayValue value=block.finalized.to_string() long_value=None/>
+        </tr>
+    }
+}
+
+/// The decoded contents of a block
+#[component]
+pub fn BlockPage() -> impl IntoView {
+    let network = use_network();
+    let params = use_params_map();
+    let block = move || params.with(|params| params.get("block").cloned().unwrap_or_default());
+    let pallet = use_pallet_filter();
+    let details = create_resource(
+        move || (network.get(), block()),
+        |(network, block)| get_block_details(network, block),
+    );
+    view! {
+        <div class="container mx-auto">
+            <A href=network_href("/blocks") class="text-sm underline">
+                "Back to recent blocks"
+            </A>
+            <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                {move || {
+                    details
+                        .get()
+                        .map(|details| match details {
+                            Err(e) => {
+                                view! { <pre class="error">"server error: " {e.to_string()}</pre> }
+                                    .into_view()
+                            }
+                            Ok(details) => {
+                                let path = format!("/block/{}", details.block.number);
+                                view! {
+                                    <BlockDetailsView details pallet=pallet.get()/>
+                                    <PalletFilter path pallet/>
+                                }
+                                    .into_view()
+                            }
+                        })
+                        .unwrap_or_default()
+                }}
+
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+fn BlockDetailsView(details: BlockDetails, pallet: Option<String>) -> impl IntoView {
+    let BlockDetails { block, parent_hash, extrinsics, events } = details;
+    let matches = move |name: &str| pallet.as_deref().map_or(true, |pallet| pallet == name);
+    let extrinsics = extrinsics
+        .into_iter()
+        .filter(|extrinsic| matches(&extrinsic.pallet))
+        .map(|extrinsic| view! { <ExtrinsicRow extrinsic/> })
+        .collect_view();
+    let events = events
+        .into_iter()
+        .filter(|event| matches(&event.pallet))
+        .map(|event| view! { <EventRow event/> })
+        .collect_view();
+    // The genesis block has no parent to link to
+    let parent = if block.number == 0 {
+        view! { <DisplayValue value=parent_hash long_value=None/> }.into_view()
+    } else {
+        let href = network_href(&format!("/block/{}", parent_hash));
+        view! { <LinkedValue value=parent_hash href/> }.into_view()
+    };
+    view! {
+        <DetailsList title=format!("Block #{}", block.number)>
+            <DetailRow label="Hash">
+                <DisplayValue value=block.hash.clone() long_value=None/>
+            </DetailRow>
+            <DetailRow label="Parent">
+                {parent}
+            </DetailRow>
+            <DetailRow label="Author">
+                <DisplayValue value=block.author.unwrap_or_else(|| "Unknown".to_string()) long_value=None/>
+            </DetailRow>
+            <DetailRow label="Time">
+                <DisplayValue
+                    value=block.timestamp.map(|ms| display_timestamp(ms / 1000)).unwrap_or_default()
+                    long_value=None
+                />
+            </DetailRow>
+            <DetailRow label="Finalized?">
+                <DisplayValue value=block.finalized.to_string() long_value=None/>
+            </DetailRow>
+        </DetailsList>
+        <DetailsTable title="Extrinsics" headings=vec!["Index", "Call", "Signer", "Result", "Arguments"]>
+            {extrinsics}
+        </DetailsTable>
+        <DetailsTable title="Events" headings=vec!["Index", "Extrinsic", "Event", "Fields"]>
+            {events}
+        </DetailsTable>
+    }
+}
+
+#[component]
+fn ExtrinsicRow(extrinsic: ExtrinsicSummary) -> impl IntoView {
+    let result = match extrinsic.success {
+        Some(true) => "Success",
+        Some(false) => "Failed",
+        None => "Unknown",
+    };
+    view! {
+        <tr class="hover:bg-gray-200 align-top">
+            <DisplayValue value=extrinsic.index.to_string() long_value=None/>
+            <DisplayValue value=format!("{}.{}", extrinsic.pallet, extrinsic.call) long_value=None/>
+            <DisplayValue value=extrinsic.signer.unwrap_or_default() long_value=None/>
+            <DisplayValue value=result.to_string() long_value=None/>
+            <td class="p-4 text-sm text-blue-gray-900">
+                <code class="break-all">{extrinsic.arguments}</code>
+            </td>
+        </tr>
+    }
+}
+
+#[component]
+fn EventRow(event: EventSummary) -> impl IntoView {
+    view! {
+        <tr class="hover:bg-gray-200 align-top">
+            <DisplayValue value=event.index.to_string() long_value=None/>
+            <DisplayValue value=event.extrinsic.map(|index| index.to_string()).unwrap_or_default() long_value=None/>
+            <DisplayValue value=format!("{}.{}", event.pallet, event.variant) long_value=None/>
+            <td class="p-4 text-sm text-blue-gray-900">
+                <code class="break-all">{event.fields}</code>
+            </td>
+        </tr>
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use anyhow::anyhow;
+        use entropy_testing_utils::chain_api::{entropy, EntropyConfig};
+        use futures::{stream, StreamExt, TryStreamExt};
+        use std::collections::{BTreeSet, HashMap};
+        use subxt::{
+            backend::legacy::LegacyRpcMethods,
+            blocks::Block,
+            config::substrate::DigestItem,
+            events::Phase,
+            utils::{AccountId32, H256},
+            OnlineClient,
+        };
+
+        /// Most blocks which can be listed at once
+        const MAX_RECENT_BLOCKS: u32 = 100;
+
+        /// How many blocks are read at the same time
+        const CONCURRENT_BLOCK_READS: usize = 8;
+
+        /// Longest decoded arguments or event fields shown before they are shortened, as these
+        /// can include whole program bytecode
+        const MAX_FIELDS_LENGTH: usize = 500;
+
+        /// Read the latest `count` blocks from the best block down, newest first. Given a pallet,
+        /// blocks are read until `count` with activity in it are found, or `MAX_SCANNED_BLOCKS`
+        /// have been read. Blocks without activity in the pallet are only decoded, not summarized.
+        pub async fn query_recent_blocks(
+            api: &OnlineClient<EntropyConfig>,
+            rpc: &LegacyRpcMethods<EntropyConfig>,
+            count: u32,
+            pallet: Option<&str>,
+        ) -> anyhow::Result<Vec<BlockSummary>> {
+            let best = rpc
+                .chain_get_header(None)
+                .await?
+                .ok_or_else(|| anyhow!("Error getting best block header"))?
+                .number;
+            let finalized = finalized_number(rpc).await?;
+            let limit = if pallet.is_some() { MAX_SCANNED_BLOCKS } else { count };
+            let oldest = best.saturating_sub(limit.saturating_sub(1));
+
+            let mut summaries = stream::iter((oldest..=best).rev())
+                .map(|number| async move {
+                    let block_hash = rpc
+                        .chain_get_block_hash(Some(number.into()))
+                        .await?
+                        .ok_or_else(|| anyhow!("No block #{}", number))?;
+                    let block = decode_block(api, block_hash).await?;
+                    if !block.involves(pallet) {
+                        return anyhow::Ok(None);
+                    }
+                    anyhow::Ok(Some(block.summarize(api, finalized).await?))
+                })
+                .buffered(CONCURRENT_BLOCK_READS);
+
+            // Dropping the stream stops reading any blocks which are no longer needed
+            let mut blocks = Vec::new();
+            while blocks.len() < count as usize {
+                match summaries.try_next().await? {
+                    Some(summary) => blocks.extend(summary),
+                    None => break,
+                }
+            }
+            Ok(blocks)
+        }
+
+        /// Read and decode everything in the given block
+        pub async fn query_block_details(
+            api: &OnlineClient<EntropyConfig>,
+            rpc: &LegacyRpcMethods<EntropyConfig>,
+            block_hash: H256,
+        ) -> anyhow::Result<BlockDetails> {
+            let finalized = finalized_number(rpc).await?;
+            let block = decode_block(api, block_hash).await?;
+            Ok(BlockDetails {
+                block: block.summarize(api, finalized).await?,
+                parent_hash: format!("{:?}", block.parent_hash),
+                extrinsics: block.extrinsics,
+                events: block.events,
+            })
+        }
+
+        async fn finalized_number(rpc: &LegacyRpcMethods<EntropyConfig>) -> anyhow::Result<u32> {
+            let finalized_hash = rpc.chain_get_finalized_head().await?;
+            Ok(rpc
+                .chain_get_header(Some(finalized_hash))
+                .await?
+                .ok_or_else(|| anyhow!("Error getting finalized block header"))?
+                .number)
+        }
+
+        /// Unix time in seconds at which a block was produced, from its timestamp inherent
+        pub async fn block_time(block: &Block<EntropyConfig, OnlineClient<EntropyConfig>>) -> anyhow::Result<u64> {
+            let timestamp = block
+                .storage()
+                .fetch(&entropy::storage().timestamp().now())
+                .await?
+                .ok_or_else(|| anyhow!("No timestamp at block #{}", block.number()))?;
+            Ok(timestamp / 1000)
+        }
+
+        /// A block's decoded extrinsics and events, before the storage reads needed to summarize it
+        struct DecodedBlock {
+            hash: H256,
+            number: u32,
+            parent_hash: H256,
+            /// Index of the BABE authority which produced the block
+            authority_index: Option<u32>,
+            extrinsics: Vec<ExtrinsicSummary>,
+            events: Vec<EventSummary>,
+            /// Pallets with calls or events in the block
+            pallets: Vec<String>,
+        }
+
+        impl DecodedBlock {
+            /// Whether the block has any activity in the given pallet, or any pallet if none is given
+            fn involves(&self, pallet: Option<&str>) -> bool {
+                pallet.map_or(true, |pallet| self.pallets.iter().any(|name| name == pallet))
+            }
+
+            /// Read the block's timestamp and author
+            async fn summarize(&self, api: &OnlineClient<EntropyConfig>, finalized: u32) -> anyhow::Result<BlockSummary> {
+                let storage = api.storage().at(self.hash);
+                let timestamp = storage.fetch(&entropy::storage().timestamp().now()).await?;
+                // BABE authorities are the session validators, in the same order
+                let author = match self.authority_index {
+                    Some(index) => storage
+                        .fetch_or_default(&entropy::storage().session().validators())
+                        .await?
+                        .get(index as usize)
+                        .map(|account| account.to_string()),
+                    None => None,
+                };
+                Ok(BlockSummary {
+                    number: self.number,
+                    hash: format!("{:?}", self.hash),
+                    author,
+                    extrinsic_count: self.extrinsics.len(),
+                    timestamp,
+                    finalized: self.number <= finalized,
+                    pallets: self.pallets.clone(),
+                })
+            }
+        }
+
+        /// Read a block and decode its extrinsics and events with the runtime metadata
+        async fn decode_block(api: &OnlineClient<EntropyConfig>, block_hash: H256) -> anyhow::Result<DecodedBlock> {
+            let block = api.blocks().at(block_hash).await?;
+            let header = block.header();
+
+            let events = block
+                .events()
+                .await?
+                .iter()
+                .map(|event| {
+                    let event = event?;
+                    let extrinsic = match event.phase() {
+                        Phase::ApplyExtrinsic(index) => Some(index),
+                        _ => None,
+                    };
+                    anyhow::Ok(EventSummary {
+                        index: event.index(),
+                        extrinsic,
+                        pallet: event.pallet_name().to_string(),
+                        variant: event.variant_name().to_string(),
+                        fields: shorten(event.field_values()?.to_string()),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // Each extrinsic ends with an event saying whether it succeeded
+            let results: HashMap<u32, bool> = events
+                .iter()
+                .filter(|event| event.pallet == "System")
+                .filter_map(|event| match event.variant.as_str() {
+                    "ExtrinsicSuccess" => Some((event.extrinsic?, true)),
+                    "ExtrinsicFailed" => Some((event.extrinsic?, false)),
+                    _ => None,
+                })
+                .collect();
+
+            let extrinsics = block
+                .extrinsics()
+                .await?
+                .iter()
+                .map(|extrinsic| {
+                    let extrinsic = extrinsic?;
+                    anyhow::Ok(ExtrinsicSummary {
+                        index: extrinsic.index(),
+                        pallet: extrinsic.pallet_name()?.to_string(),
+                        call: extrinsic.variant_name()?.to_string(),
+                        signer: extrinsic.address_bytes().and_then(decode_signer),
+                        success: results.get(&extrinsic.index()).copied(),
+                        arguments: shorten(extrinsic.field_values()?.to_string()),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let pallets: BTreeSet<String> = extrinsics
+                .iter()
+                .map(|extrinsic| extrinsic.pallet.clone())
+                .chain(events.iter().map(|event| event.pallet.clone()))
+                .collect();
+
+            Ok(DecodedBlock {
+                hash: block_hash,
+                number: header.number,
+                parent_hash: header.parent_hash,
+                authority_index: babe_authority_index(&header.digest.logs),
+                extrinsics,
+                events,
+                pallets: pallets.into_iter().collect(),
+            })
+        }
+
+        /// The index of the authority which produced the block, from its BABE pre-runtime digest.
+        /// Each kind of BABE pre-digest starts with the authority index after the variant byte.
+        fn babe_authority_index(logs: &[DigestItem]) -> Option<u32> {
+            logs.iter().find_map(|item| match item {
+                DigestItem::PreRuntime(engine, data) if engine == b"BABE" => {
+                    data.get(1..5)?.try_into().ok().map(u32::from_le_bytes)
+                }
+                _ => None,
+            })
+        }
+
+        /// The account which signed an extrinsic, if its address is an account ID
+        pub fn decode_signer(address: &[u8]) -> Option<String> {
+            // A `MultiAddress::Id` is the variant byte 0 followed by the account ID
+            match address.split_first() {
+                Some((0, account_id)) => {
+                    let account_id: [u8; 32] = account_id.try_into().ok()?;
+                    Some(AccountId32(account_id).to_string())
+                }
+                _ => None,
+            }
+        }
+
+        fn shorten(mut text: String) -> String {
+            if text.len() > MAX_FIELDS_LENGTH {
+                let mut end = MAX_FIELDS_LENGTH;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                text.truncate(end);
+                text.push('…');
+            }
+            text
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use crate::display_timestamp;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+/// How well we are currently able to talk to the chain endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub enum ChainStatus {
+    /// The first connection attempt has not finished yet, so it is not known whether the chain
+    /// can be reached
+    Connecting,
+    /// Connected without any problems
+    Connected,
+    /// Connected, but only after retrying
+    Degraded {
+        failed_attempts: u32,
+        last_error: String,
+    },
+    /// Could not connect within the retry budget
+    Unreachable {
+        last_error: String,
+        /// Unix time in seconds of the last successful connection, if there has been one
+        last_success: Option<u64>,
+    },
+}
+
+impl ChainStatus {
+    pub fn is_connected(&self) -> bool {
+        matches!(self, ChainStatus::Connected)
+    }
+
+    /// Whether we currently have a connection, even if it took retries to make
+    pub fn is_reachable(&self) -> bool {
+        matches!(self, ChainStatus::Connected | ChainStatus::Degraded { .. })
+    }
+
+    /// Whether we have not yet tried connecting, so know nothing about the chain
+    pub fn is_connecting(&self) -> bool {
+        matches!(self, ChainStatus::Connecting)
+    }
+}
+
+#[server(GetChainStatus, "/api")]
+pub async fn get_chain_status(network: String) -> Result<ChainStatus, ServerFnError> {
+    let client = crate::chain_client(&network)?;
+    // Make sure we have attempted a connection so the status is current
+    let _ = client.get().await;
+    Ok(client.status())
+}
+
+/// A banner explaining that the chain cannot currently be reached. Renders nothing when connected.
+#[component]
+pub fn ChainStatusBanner(status: ChainStatus) -> impl IntoView {
+    match status {
+        ChainStatus::Connecting | ChainStatus::Connected => ().into_view(),
+        ChainStatus::Degraded {
+            failed_attempts,
+            last_error,
+        } => view! {
+            <div class="my-4 p-4 rounded-xl bg-amber-500/20 text-amber-900" title=last_error>
+                <p class="font-bold">"Chain endpoint degraded"</p>
+                <p class="text-sm">
+                    {format!("Connected after {} failed attempt(s). Data may be slow to load.", failed_attempts)}
+                </p>
+            </div>
+        }
+        .into_view(),
+        ChainStatus::Unreachable {
+            last_error,
+            last_success,
+        } => view! {
+            <div class="my-4 p-4 rounded-xl bg-red-500/20 text-red-900" title=last_error>
+                <p class="font-bold">"Chain endpoint unreachable"</p>
+                <p class="text-sm">
+                    {match last_success {
+                        Some(time) => format!("Last successful fetch: {}", display_timestamp(time)),
+                        None => "No successful fetch since the server started.".to_string(),
+                    }}
+                </p>
+            </div>
+        }
+        .into_view(),
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use entropy_testing_utils::{
+            chain_api::EntropyConfig,
+            test_client::{get_api, get_rpc},
+        };
+        use std::{
+            sync::{
+                atomic::{AtomicU64, Ordering},
+                Arc, RwLock,
+            },
+            time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+        };
+        use subxt::{backend::legacy::LegacyRpcMethods, OnlineClient};
+        use thiserror::Error;
+        use tokio::sync::Mutex;
+
+        /// How many times to try connecting before giving up
+        const MAX_ATTEMPTS: u32 = 3;
+        /// Delay before the first retry, doubled on each subsequent retry
+        const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+        /// How long to wait for the chain to report block heights
+        const BLOCK_HEIGHT_TIMEOUT: Duration = Duration::from_secs(5);
+        /// How long after failing to connect before trying again. Until then the failure is
+        /// returned straight away, rather than every caller waiting on its own attempts.
+        const CONNECT_COOL_DOWN: Duration = Duration::from_secs(15);
+
+        pub type ApiRpc = (OnlineClient<EntropyConfig>, LegacyRpcMethods<EntropyConfig>);
+
+        #[derive(Clone, Debug, Error)]
+        pub enum ChainError {
+            #[error("Timed out after {0:?}")]
+            Timeout(Duration),
+            #[error("Connection failed: {0}")]
+            Connection(String),
+            #[error("Chain endpoint {endpoint} unreachable after {attempts} attempts: {last_error}")]
+            Unreachable {
+                endpoint: String,
+                attempts: u32,
+                last_error: Box<ChainError>,
+            },
+        }
+
+        /// Counts of requests made to the chain endpoint, for exporting as metrics
+        #[derive(Default)]
+        pub struct RpcStats {
+            requests: AtomicU64,
+            errors: AtomicU64,
+            latency_ms_total: AtomicU64,
+        }
+
+        impl RpcStats {
+            /// Record a request which was started at `started`
+            pub fn record(&self, started: Instant, succeeded: bool) {
+                self.requests.fetch_add(1, Ordering::Relaxed);
+                self.latency_ms_total
+                    .fetch_add(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+                if !succeeded {
+                    self.errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
+            pub fn requests(&self) -> u64 {
+                self.requests.load(Ordering::Relaxed)
+            }
+
+            pub fn errors(&self) -> u64 {
+                self.errors.load(Ordering::Relaxed)
+            }
+
+            /// Total time spent waiting on requests
+            pub fn latency_ms_total(&self) -> u64 {
+                self.latency_ms_total.load(Ordering::Relaxed)
+            }
+        }
+
+        /// A process-wide chain client, shared by all server functions through the axum state.
+        ///
+        /// The connection is made lazily on first use and re-made whenever the health check
+        /// finds it has dropped.
+        #[derive(Clone)]
+        pub struct ChainClient {
+            endpoint: String,
+            connection: Arc<Mutex<Option<ApiRpc>>>,
+            /// When connecting last failed and why, cleared once connected
+            last_failure: Arc<RwLock<Option<(Instant, ChainError)>>>,
+            status: Arc<RwLock<ChainStatus>>,
+            last_success: Arc<RwLock<Option<u64>>>,
+            /// The block heights read by the latest health check, if the chain answered
+            heights: Arc<RwLock<Option<(u32, u32)>>>,
+            stats: Arc<RpcStats>,
+            /// How long to wait for a single connection attempt or health check
+            connect_timeout: Duration,
+            /// How often the open connection is checked
+            health_check_interval: Duration,
+        }
+
+        impl ChainClient {
+            pub fn new(endpoint: String, connect_timeout: Duration, health_check_interval: Duration) -> Self {
+                ChainClient {
+                    endpoint,
+                    connection: Arc::new(Mutex::new(None)),
+                    last_failure: Arc::new(RwLock::new(None)),
+                    status: Arc::new(RwLock::new(ChainStatus::Connecting)),
+                    last_success: Arc::new(RwLock::new(None)),
+                    heights: Arc::new(RwLock::new(None)),
+                    stats: Default::default(),
+                    connect_timeout,
+                    health_check_interval,
+                }
+            }
+
+            pub fn endpoint(&self) -> &str {
+                &self.endpoint
+            }
+
+            /// Get the open connection, connecting first if needed. Shortly after failing to
+            /// connect, the failure is returned without trying again.
+            pub async fn get(&self) -> Result<ApiRpc, ChainError> {
+                self.check_cool_down()?;
+                let mut connection = self.connection.lock().await;
+                if let Some(api_rpc) = connection.as_ref() {
+                    return Ok(api_rpc.clone());
+                }
+                // Connecting may have failed while waiting for the lock
+                self.check_cool_down()?;
+                let result = self.connect().await;
+                if let Ok(mut last_failure) = self.last_failure.write() {
+                    *last_failure = result.as_ref().err().map(|error| (Instant::now(), error.clone()));
+                }
+                let api_rpc = result?;
+                *connection = Some(api_rpc.clone());
+                Ok(api_rpc)
+            }
+
+            /// The last connection failure, if it was too recent to try again
+            fn check_cool_down(&self) -> Result<(), ChainError> {
+                match self.last_failure.read().ok().as_deref().and_then(Option::as_ref) {
+                    Some((failed_at, error)) if failed_at.elapsed() < CONNECT_COOL_DOWN => Err(error.clone()),
+                    _ => Ok(()),
+                }
+            }
+
+            /// Drop the current connection so that the next use makes a new one
+            pub async fn reset(&self) {
+                *self.connection.lock().await = None;
+            }
+
+            pub fn status(&self) -> ChainStatus {
+                match self.status.read() {
+                    Ok(status) => status.clone(),
+                    Err(poisoned) => poisoned.into_inner().clone(),
+                }
+            }
+
+            /// Unix time in seconds of the last successful connection or health check
+            pub fn last_success(&self) -> Option<u64> {
+                self.last_success.read().ok().and_then(|time| *time)
+            }
+
+            /// Request counts, including connection attempts and health checks
+            pub fn stats(&self) -> &RpcStats {
+                &self.stats
+            }
+
+            /// The best and finalized block numbers, if the chain answers in time
+            pub async fn block_heights(&self) -> Option<(u32, u32)> {
+                let started = Instant::now();
+                let heights = tokio::time::timeout(BLOCK_HEIGHT_TIMEOUT, async {
+                    let (_api, rpc) = self.get().await.ok()?;
+                    let best = rpc.chain_get_header(None).await.ok()??;
+                    let finalized_hash = rpc.chain_get_finalized_head().await.ok()?;
+                    let finalized = rpc.chain_get_header(Some(finalized_hash)).await.ok()??;
+                    Some((best.number, finalized.number))
+                })
+                .await
+                .ok()
+                .flatten();
+                self.stats.record(started, heights.is_some());
+                heights
+            }
+
+            /// The best and finalized block numbers from the latest health check, without asking
+            /// the chain
+            pub fn last_block_heights(&self) -> Option<(u32, u32)> {
+                self.heights.read().ok().and_then(|heights| *heights)
+            }
+
+            fn set_heights(&self, heights: Option<(u32, u32)>) {
+                if let Ok(mut current) = self.heights.write() {
+                    *current = heights;
+                }
+            }
+
+            fn set_status(&self, status: ChainStatus) {
+                if !matches!(status, ChainStatus::Unreachable { .. }) {
+                    if let Ok(mut last_success) = self.last_success.write() {
+                        *last_success = Some(unix_now());
+                    }
+                }
+                if let Ok(mut current) = self.status.write() {
+                    *current = status;
+                }
+            }
+
+            /// Periodically check the connection, reconnecting when the websocket has dropped, and
+            /// read the block heights. Runs forever, so should be spawned.
+            pub async fn watch(self) {
+                let mut interval = tokio::time::interval(self.health_check_interval);
+                loop {
+                    interval.tick().await;
+                    let (_api, rpc) = match self.get().await {
+                        Ok(api_rpc) => api_rpc,
+                        Err(error) => {
+                            log::warn!("{}", error);
+                            self.set_heights(None);
+                            continue;
+                        }
+                    };
+                    let started = Instant::now();
+                    let health = tokio::time::timeout(self.connect_timeout, rpc.system_health()).await;
+                    self.stats.record(started, matches!(health, Ok(Ok(_))));
+                    match health {
+                        Ok(Ok(_)) => {
+                            if !self.status().is_connected() {
+                                log::info!("Chain endpoint {} recovered", self.endpoint);
+                            }
+                            self.set_status(ChainStatus::Connected);
+                            self.set_heights(self.block_heights().await);
+                        }
+                        Ok(Err(error)) => {
+                            log::warn!("Chain connection dropped, reconnecting: {}", error);
+                            self.set_heights(None);
+                            self.reset().await;
+                        }
+                        Err(_) => {
+                            log::warn!("Chain health check timed out, reconnecting");
+                            self.set_heights(None);
+                            self.reset().await;
+                        }
+                    }
+                }
+            }
+
+            /// Connect to the chain, retrying with exponential backoff
+            async fn connect(&self) -> Result<ApiRpc, ChainError> {
+                let mut backoff = INITIAL_BACKOFF;
+                let mut last_error = None;
+                for attempt in 0..MAX_ATTEMPTS {
+                    if attempt > 0 {
+                        tokio::time::sleep(backoff).await;
+                        backoff *= 2;
+                    }
+                    let started = Instant::now();
+                    let result = connect_once(&self.endpoint, self.connect_timeout).await;
+                    self.stats.record(started, result.is_ok());
+                    match result {
+                        Ok(api_rpc) => {
+                            self.set_status(match last_error {
+                                Some(error) => ChainStatus::Degraded {
+                                    failed_attempts: attempt,
+                                    last_error: error.to_string(),
+                                },
+                                None => ChainStatus::Connected,
+                            });
+                            return Ok(api_rpc);
+                        }
+                        Err(error) => {
+                            log::warn!("Connection attempt {} to {} failed: {}", attempt + 1, self.endpoint, error);
+                            last_error = Some(error);
+                        }
+                    }
+                }
+                let error = ChainError::Unreachable {
+                    endpoint: self.endpoint.clone(),
+                    attempts: MAX_ATTEMPTS,
+                    last_error: Box::new(last_error.unwrap_or(ChainError::Timeout(self.connect_timeout))),
+                };
+                self.set_status(ChainStatus::Unreachable {
+                    last_error: error.to_string(),
+                    last_success: self.last_success(),
+                });
+                Err(error)
+            }
+        }
+
+        /// A single connection attempt with a timeout. The connection is made on its own task so
+        /// that a panic inside the client is reported as an error rather than taking down the
+        /// request. The task is aborted on timeout, so abandoned attempts do not pile up.
+        async fn connect_once(endpoint: &str, timeout: Duration) -> Result<ApiRpc, ChainError> {
+            let endpoint = endpoint.to_string();
+            let mut task = tokio::spawn(async move {
+                let api = get_api(&endpoint).await?;
+                let rpc = get_rpc(&endpoint).await?;
+                Ok::<_, subxt::Error>((api, rpc))
+            });
+            match tokio::time::timeout(timeout, &mut task).await {
+                Err(_) => {
+                    task.abort();
+                    Err(ChainError::Timeout(timeout))
+                }
+                Ok(Err(join_error)) => Err(ChainError::Connection(join_error.to_string())),
+                Ok(Ok(Err(error))) => Err(ChainError::Connection(error.to_string())),
+                Ok(Ok(Ok(api_rpc))) => Ok(api_rpc),
+            }
+        }
+
+        pub fn unix_now() -> u64 {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default()
+        }
+    }
+}
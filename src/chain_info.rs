@@ -0,0 +1,153 @@
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+/// Number of blocks over which the average block time is measured
+pub const BLOCK_TIME_SAMPLE: u32 = 10;
+
+/// General information about the chain and the node we are connected to
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub struct ChainInfo {
+    pub chain_name: String,
+    pub node_version: String,
+    pub spec_version: u32,
+    pub transaction_version: u32,
+    pub best_block: u32,
+    pub finalized_block: u32,
+    /// Average time between the last `BLOCK_TIME_SAMPLE` blocks, if we could read the timestamps
+    pub average_block_time_ms: Option<u64>,
+    pub peers: usize,
+}
+
+impl ChainInfo {
+    /// How many blocks finality is behind the best block
+    pub fn finality_lag(&self) -> u32 {
+        self.best_block.saturating_sub(self.finalized_block)
+    }
+}
+
+#[server(GetChainInfo, "/api")]
+pub async fn get_chain_info(network: String) -> Result<ChainInfo, ServerFnError> {
+    use crate::get_api_rpc;
+
+    let (api, rpc) = get_api_rpc(&network).await?;
+
+    query_chain_info(&api, &rpc)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// A single labelled figure in the overview panel
+#[component]
+fn Stat(label: &'static str, value: String) -> impl IntoView {
+    view! {
+        <div class="p-4">
+            <p class="block font-sans text-sm antialiased font-normal leading-none text-blue-gray-900 opacity-70">
+                {label}
+            </p>
+            <p class="block font-sans text-lg antialiased font-normal leading-normal text-blue-gray-900">
+                {value}
+            </p>
+        </div>
+    }
+}
+
+#[component]
+pub fn ChainOverview(info: ChainInfo) -> impl IntoView {
+    let finality_lag = info.finality_lag();
+    view! {
+        <h2 class="my-4 block font-sans text-xl antialiased leading-snug tracking-normal mt-4 text-gray-700">
+            "Chain Overview"
+        </h2>
+        <div class="grid grid-cols-2 md:grid-cols-4 text-gray-700 bg-white shadow-md rounded-xl bg-clip-border">
+            <Stat label="Chain" value=info.chain_name/>
+            <Stat label="Node Version" value=info.node_version/>
+            <Stat
+                label="Runtime Version"
+                value=format!("spec {} / tx {}", info.spec_version, info.transaction_version)
+            />
+            <Stat label="Peers" value=info.peers.to_string()/>
+            <Stat label="Best Block" value=format!("#{}", info.best_block)/>
+            <Stat label="Finalized Block" value=format!("#{}", info.finalized_block)/>
+            <Stat label="Finality Lag" value=format!("{} blocks", finality_lag)/>
+            <Stat
+                label="Average Block Time"
+                value=info
+                    .average_block_time_ms
+                    .map(|ms| format!("{:.1}s", ms as f64 / 1000.0))
+                    .unwrap_or("Unknown".to_string())
+            />
+        </div>
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use anyhow::anyhow;
+        use entropy_testing_utils::chain_api::EntropyConfig;
+        use parity_scale_codec::Decode;
+        use subxt::{backend::legacy::LegacyRpcMethods, utils::H256, OnlineClient};
+
+        pub async fn query_chain_info(
+            api: &OnlineClient<EntropyConfig>,
+            rpc: &LegacyRpcMethods<EntropyConfig>,
+        ) -> anyhow::Result<ChainInfo> {
+            let best_header = rpc
+                .chain_get_header(None)
+                .await?
+                .ok_or_else(|| anyhow!("Error getting best block header"))?;
+            let finalized_hash = rpc.chain_get_finalized_head().await?;
+            let finalized_header = rpc
+                .chain_get_header(Some(finalized_hash))
+                .await?
+                .ok_or_else(|| anyhow!("Error getting finalized block header"))?;
+            let runtime_version = rpc.state_get_runtime_version(None).await?;
+
+            Ok(ChainInfo {
+                chain_name: rpc.system_chain().await?,
+                node_version: rpc.system_version().await?,
+                spec_version: runtime_version.spec_version,
+                transaction_version: runtime_version.transaction_version,
+                best_block: best_header.number,
+                finalized_block: finalized_header.number,
+                average_block_time_ms: average_block_time(api, rpc, best_header.number).await.ok(),
+                peers: rpc.system_health().await?.peers,
+            })
+        }
+
+        /// Average milliseconds per block, measured from on-chain timestamps
+        async fn average_block_time(
+            api: &OnlineClient<EntropyConfig>,
+            rpc: &LegacyRpcMethods<EntropyConfig>,
+            best_block: u32,
+        ) -> anyhow::Result<u64> {
+            let sample = BLOCK_TIME_SAMPLE.min(best_block);
+            if sample == 0 {
+                return Err(anyhow!("Not enough blocks to measure block time"));
+            }
+            let latest = block_timestamp(api, rpc, best_block).await?;
+            let earliest = block_timestamp(api, rpc, best_block - sample).await?;
+            Ok(latest.saturating_sub(earliest) / sample as u64)
+        }
+
+        /// The timestamp set in the given block, in milliseconds
+        pub async fn block_timestamp(
+            api: &OnlineClient<EntropyConfig>,
+            rpc: &LegacyRpcMethods<EntropyConfig>,
+            block_number: u32,
+        ) -> anyhow::Result<u64> {
+            let block_hash: H256 = rpc
+                .chain_get_block_hash(Some(block_number.into()))
+                .await?
+                .ok_or_else(|| anyhow!("No block #{}", block_number))?;
+            let storage_address = subxt::dynamic::storage("Timestamp", "Now", Vec::<()>::new());
+            let timestamp = api
+                .storage()
+                .at(block_hash)
+                .fetch(&storage_address)
+                .await?
+                .ok_or_else(|| anyhow!("No timestamp at block #{}", block_number))?;
+            Ok(u64::decode(&mut timestamp.into_encoded().as_ref())?)
+        }
+    }
+}
@@ -0,0 +1,427 @@
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    use anyhow::{anyhow, bail, Context};
+    use clap::Parser;
+    use serde::Deserialize;
+    use std::{net::SocketAddr, path::PathBuf, time::Duration};
+
+    /// Names which cannot be used for a network, as the paths they would be served at are taken
+    /// by other routes or by the pages of the network itself
+    const RESERVED_NETWORK_NAMES: &[&str] = &[
+        "account", "admin", "api", "api-docs", "block", "blocks", "diff", "events", "favicon.ico", "history",
+        "metrics", "openapi.json", "pkg", "program", "signing", "v1", "validator",
+    ];
+
+    /// Command line flags. Each can also be given as an environment variable, and takes
+    /// precedence over the config file.
+    #[derive(Debug, Parser)]
+    #[command(about = "Serves the Entropy network status page")]
+    pub struct Cli {
+        /// TOML config file
+        #[arg(short, long, env = "STATUS_PAGE_CONFIG")]
+        pub config: Option<PathBuf>,
+        /// Websocket endpoint of the chain node to read from, when no networks are configured
+        #[arg(long, env = "ENTROPY_TESTNET_ENDPOINT")]
+        pub chain_endpoint: Option<String>,
+        /// Address to serve the site on
+        #[arg(long, env = "LEPTOS_SITE_ADDR")]
+        pub site_addr: Option<SocketAddr>,
+        /// Directory holding the TLS certificate and private key. HTTPS is served when given.
+        #[arg(long, env = "TLS_CERT_LOCATION")]
+        pub tls_cert_dir: Option<PathBuf>,
+        /// Port on which plain HTTP requests are redirected to HTTPS, or 0 for none. Needs TLS.
+        #[arg(long, env = "HTTP_REDIRECT_PORT")]
+        pub http_redirect_port: Option<u16>,
+        /// SQLite database for history and announcements
+        #[arg(long, env = "DATABASE_PATH")]
+        pub database_path: Option<PathBuf>,
+        /// Days of history to keep
+        #[arg(long, env = "HISTORY_RETENTION_DAYS")]
+        pub history_retention_days: Option<u64>,
+        /// Token required by the admin API, which is disabled without one
+        #[arg(long, env = "ADMIN_TOKEN", hide_env_values = true)]
+        pub admin_token: Option<String>,
+        /// Comma separated URLs to post alerts to
+        #[arg(long, env = "ALERT_WEBHOOKS", value_delimiter = ',')]
+        pub alert_webhooks: Option<Vec<String>>,
+        /// Alert when finality falls this many blocks behind the best block
+        #[arg(long, env = "ALERT_FINALITY_LAG_BLOCKS")]
+        pub alert_finality_lag_blocks: Option<u32>,
+        /// Alert when a threshold server has been down for this many minutes
+        #[arg(long, env = "ALERT_TSS_DOWN_MINUTES")]
+        pub alert_tss_down_minutes: Option<u64>,
+    }
+
+    /// Server configuration, as read from the config file
+    #[derive(Debug, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct Config {
+        /// Overrides the address set by cargo-leptos
+        pub site_addr: Option<SocketAddr>,
+        pub database_path: PathBuf,
+        pub admin_token: Option<String>,
+        pub chain: ChainConfig,
+        /// Networks to monitor. The first is shown by default. When none are given, the
+        /// network at `chain.endpoint` is monitored as `testnet`.
+        pub networks: Vec<NetworkConfig>,
+        pub tls: Option<TlsConfig>,
+        pub probes: ProbeConfig,
+        pub history: HistoryConfig,
+        pub alerts: AlertsConfig,
+        pub signing: SigningConfig,
+        pub features: Features,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct ChainConfig {
+        pub endpoint: String,
+        pub connect_timeout_secs: u64,
+        pub health_check_interval_secs: u64,
+    }
+
+    /// A chain to monitor, shown under `/<name>/`
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct NetworkConfig {
+        /// Used in URLs, so lowercase letters, digits and dashes only
+        pub name: String,
+        /// Shown in the network switcher. Defaults to the name.
+        pub label: Option<String>,
+        pub endpoint: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct TlsConfig {
+        pub cert_dir: PathBuf,
+        #[serde(default = "default_cert_file")]
+        pub cert_file: String,
+        #[serde(default = "default_key_file")]
+        pub key_file: String,
+        /// Port on which plain HTTP requests are redirected to HTTPS. Set to 0 for none.
+        #[serde(default = "default_http_redirect_port")]
+        pub http_redirect_port: Option<u16>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct ProbeConfig {
+        pub interval_secs: u64,
+        pub timeout_secs: u64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct HistoryConfig {
+        pub record_interval_secs: u64,
+        pub retention_days: u64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct AlertsConfig {
+        pub webhooks: Vec<String>,
+        pub evaluation_interval_secs: u64,
+        pub finality_lag_blocks: u32,
+        pub tss_down_minutes: u64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct SigningConfig {
+        /// Events recording signature requests, as `Pallet.Event`. Signing happens between the
+        /// threshold servers, so which events record it on chain depends on the runtime.
+        pub events: Vec<String>,
+    }
+
+    /// Parts of the server which can be switched off
+    #[derive(Debug, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct Features {
+        /// The `/v1` JSON API and its OpenAPI document
+        pub api: bool,
+        /// Prometheus metrics at `/metrics`
+        pub metrics: bool,
+        /// Probing each validator's threshold server
+        pub probes: bool,
+        /// Recording history for the charts and uptime page
+        pub history: bool,
+        /// Recording signature requests for the signing activity page
+        pub signing: bool,
+    }
+
+    impl Default for Config {
+        fn default() -> Self {
+            Config {
+                site_addr: None,
+                database_path: default_database_path(),
+                admin_token: None,
+                chain: Default::default(),
+                networks: Vec::new(),
+                tls: None,
+                probes: Default::default(),
+                history: Default::default(),
+                alerts: Default::default(),
+                signing: Default::default(),
+                features: Default::default(),
+            }
+        }
+    }
+
+    impl Default for ChainConfig {
+        fn default() -> Self {
+            ChainConfig {
+                endpoint: "ws://localhost:9944".to_string(),
+                connect_timeout_secs: 10,
+                health_check_interval_secs: 15,
+            }
+        }
+    }
+
+    impl Default for ProbeConfig {
+        fn default() -> Self {
+            ProbeConfig {
+                interval_secs: 30,
+                timeout_secs: 5,
+            }
+        }
+    }
+
+    impl Default for HistoryConfig {
+        fn default() -> Self {
+            HistoryConfig {
+                record_interval_secs: 5 * 60,
+                retention_days: 90,
+            }
+        }
+    }
+
+    impl Default for AlertsConfig {
+        fn default() -> Self {
+            AlertsConfig {
+                webhooks: Vec::new(),
+                evaluation_interval_secs: 30,
+                finality_lag_blocks: 10,
+                tss_down_minutes: 5,
+            }
+        }
+    }
+
+    impl Default for SigningConfig {
+        fn default() -> Self {
+            SigningConfig {
+                events: vec!["Relayer.SignatureRequested".to_string()],
+            }
+        }
+    }
+
+    impl Default for Features {
+        fn default() -> Self {
+            Features {
+                api: true,
+                metrics: true,
+                probes: true,
+                history: true,
+                signing: true,
+            }
+        }
+    }
+
+    fn default_cert_file() -> String {
+        "fullchain.pem".to_string()
+    }
+
+    fn default_key_file() -> String {
+        "privkey.pem".to_string()
+    }
+
+    fn default_database_path() -> PathBuf {
+        PathBuf::from("status.sqlite")
+    }
+
+    fn default_http_redirect_port() -> Option<u16> {
+        Some(80)
+    }
+
+    impl Config {
+        /// Read the config file given on the command line, if any, apply the command line and
+        /// environment overrides, and check the result makes sense
+        pub fn load() -> anyhow::Result<Self> {
+            let cli = Cli::parse();
+            let mut config = match &cli.config {
+                Some(path) => {
+                    let contents = std::fs::read_to_string(path)
+                        .with_context(|| format!("Cannot read config file {}", path.display()))?;
+                    toml::from_str(&contents)
+                        .with_context(|| format!("Invalid config file {}", path.display()))?
+                }
+                None => Config::default(),
+            };
+            let redirects = matches!(cli.http_redirect_port, Some(port) if port != 0);
+            if redirects && cli.tls_cert_dir.is_none() && config.tls.is_none() {
+                bail!("--http-redirect-port needs TLS to be configured, with --tls-cert-dir or tls.cert_dir");
+            }
+            if cli.chain_endpoint.is_some() && !config.networks.is_empty() {
+                bail!("--chain-endpoint is only used without networks in the config file, so give each network's endpoint there instead");
+            }
+            config.apply(cli);
+            config.validate()?;
+            Ok(config)
+        }
+
+        fn apply(&mut self, cli: Cli) {
+            if let Some(endpoint) = cli.chain_endpoint {
+                self.chain.endpoint = endpoint;
+            }
+            if let Some(site_addr) = cli.site_addr {
+                self.site_addr = Some(site_addr);
+            }
+            if let Some(cert_dir) = cli.tls_cert_dir {
+                match &mut self.tls {
+                    Some(tls) => tls.cert_dir = cert_dir,
+                    None => {
+                        self.tls = Some(TlsConfig {
+                            cert_dir,
+                            cert_file: default_cert_file(),
+                            key_file: default_key_file(),
+                            http_redirect_port: default_http_redirect_port(),
+                        })
+                    }
+                }
+            }
+            if let Some(tls) = &mut self.tls {
+                if let Some(port) = cli.http_redirect_port {
+                    tls.http_redirect_port = Some(port);
+                }
+                // Port 0 turns the redirect off
+                tls.http_redirect_port = tls.http_redirect_port.filter(|port| *port != 0);
+            }
+            if let Some(database_path) = cli.database_path {
+                self.database_path = database_path;
+            }
+            if let Some(retention_days) = cli.history_retention_days {
+                self.history.retention_days = retention_days;
+            }
+            if let Some(admin_token) = cli.admin_token {
+                self.admin_token = Some(admin_token);
+            }
+            if let Some(webhooks) = cli.alert_webhooks {
+                self.alerts.webhooks = webhooks;
+            }
+            if let Some(blocks) = cli.alert_finality_lag_blocks {
+                self.alerts.finality_lag_blocks = blocks;
+            }
+            if let Some(minutes) = cli.alert_tss_down_minutes {
+                self.alerts.tss_down_minutes = minutes;
+            }
+            if self.networks.is_empty() {
+                self.networks.push(NetworkConfig {
+                    name: "testnet".to_string(),
+                    label: Some("Testnet".to_string()),
+                    endpoint: self.chain.endpoint.clone(),
+                });
+            }
+            // Treat empty values, as from an empty environment variable, as not given
+            self.admin_token = self.admin_token.take().filter(|token| !token.is_empty());
+            self.alerts.webhooks = std::mem::take(&mut self.alerts.webhooks)
+                .into_iter()
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty())
+                .collect();
+        }
+
+        fn validate(&self) -> anyhow::Result<()> {
+            for (index, network) in self.networks.iter().enumerate() {
+                let valid_name = !network.name.is_empty()
+                    && network
+                        .name
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+                if !valid_name {
+                    bail!("Network name {:?} should only use lowercase letters, digits and dashes", network.name);
+                }
+                if RESERVED_NETWORK_NAMES.contains(&network.name.as_str()) {
+                    bail!("Network name {:?} is reserved, please choose another", network.name);
+                }
+                if self.networks[..index].iter().any(|other| other.name == network.name) {
+                    bail!("Network {} is configured more than once", network.name);
+                }
+                if !(network.endpoint.starts_with("ws://") || network.endpoint.starts_with("wss://")) {
+                    bail!("Endpoint of network {} should be a ws:// or wss:// URL, not {}", network.name, network.endpoint);
+                }
+            }
+            for (name, value) in [
+                ("chain.connect_timeout_secs", self.chain.connect_timeout_secs),
+                ("chain.health_check_interval_secs", self.chain.health_check_interval_secs),
+                ("probes.interval_secs", self.probes.interval_secs),
+                ("probes.timeout_secs", self.probes.timeout_secs),
+                ("history.record_interval_secs", self.history.record_interval_secs),
+                ("history.retention_days", self.history.retention_days),
+                ("alerts.evaluation_interval_secs", self.alerts.evaluation_interval_secs),
+            ] {
+                if value == 0 {
+                    bail!("{} should be greater than zero", name);
+                }
+            }
+            if let Some(tls) = &self.tls {
+                for path in [tls.cert_path(), tls.key_path()] {
+                    if !path.is_file() {
+                        bail!("TLS file {} does not exist", path.display());
+                    }
+                }
+            }
+            for event in &self.signing.events {
+                if !matches!(event.split_once('.'), Some((pallet, name)) if !pallet.is_empty() && !name.is_empty()) {
+                    bail!("Signing event {:?} should be given as Pallet.Event", event);
+                }
+            }
+            for webhook in &self.alerts.webhooks {
+                reqwest::Url::parse(webhook).map_err(|e| anyhow!("Invalid alert webhook URL {}: {}", webhook, e))?;
+            }
+            Ok(())
+        }
+
+        /// How long to wait for the chain before giving up on a connection attempt
+        pub fn connect_timeout(&self) -> Duration {
+            Duration::from_secs(self.chain.connect_timeout_secs)
+        }
+
+        pub fn health_check_interval(&self) -> Duration {
+            Duration::from_secs(self.chain.health_check_interval_secs)
+        }
+
+        pub fn probe_interval(&self) -> Duration {
+            Duration::from_secs(self.probes.interval_secs)
+        }
+
+        pub fn probe_timeout(&self) -> Duration {
+            Duration::from_secs(self.probes.timeout_secs)
+        }
+
+        pub fn record_interval(&self) -> Duration {
+            Duration::from_secs(self.history.record_interval_secs)
+        }
+
+        pub fn history_retention(&self) -> Duration {
+            Duration::from_secs(self.history.retention_days * 24 * 60 * 60)
+        }
+    }
+
+    impl NetworkConfig {
+        pub fn label(&self) -> String {
+            self.label.clone().unwrap_or_else(|| self.name.clone())
+        }
+    }
+
+    impl TlsConfig {
+        pub fn cert_path(&self) -> PathBuf {
+            self.cert_dir.join(&self.cert_file)
+        }
+
+        pub fn key_path(&self) -> PathBuf {
+            self.cert_dir.join(&self.key_file)
+        }
+    }
+}}
@@ -0,0 +1,44 @@
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    use anyhow::Context;
+    use rusqlite::Connection;
+    use std::{
+        path::Path,
+        sync::{Arc, Mutex},
+    };
+
+    /// The local SQLite database shared by everything the server keeps between restarts
+    #[derive(Clone)]
+    pub struct Database(Arc<Mutex<Connection>>);
+
+    impl Database {
+        pub fn open(path: &Path) -> anyhow::Result<Self> {
+            let connection =
+                Connection::open(path).with_context(|| format!("Cannot open database {}", path.display()))?;
+            Ok(Database(Arc::new(Mutex::new(connection))))
+        }
+
+        /// Create any missing tables. Only for use at startup, as it blocks.
+        pub fn create_tables(&self, schema: &str) -> anyhow::Result<()> {
+            self.0
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Database lock poisoned"))?
+                .execute_batch(schema)?;
+            Ok(())
+        }
+
+        /// Run a query on a blocking thread, as SQLite calls block
+        pub async fn with_connection<T: Send + 'static>(
+            &self,
+            query: impl FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+        ) -> anyhow::Result<T> {
+            let connection = self.0.clone();
+            tokio::task::spawn_blocking(move || {
+                let connection = connection.lock().map_err(|_| anyhow::anyhow!("Database lock poisoned"))?;
+                Ok(query(&connection)?)
+            })
+            .await?
+        }
+    }
+}}
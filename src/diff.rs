@@ -0,0 +1,353 @@
+use crate::{
+    indexer::{BlockRef, Snapshot},
+    network::{network_href, use_network},
+    program::Program,
+    registered_account::RegisteredAccount,
+    validator::Validator,
+    DetailsTable,
+};
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How an entry differs between the two blocks
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ChangeKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "Added",
+            ChangeKind::Removed => "Removed",
+            ChangeKind::Modified => "Modified",
+        }
+    }
+
+    fn class(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "px-2 py-1 rounded-md text-sm bg-green-100 text-green-900",
+            ChangeKind::Removed => "px-2 py-1 rounded-md text-sm bg-red-100 text-red-900",
+            ChangeKind::Modified => "px-2 py-1 rounded-md text-sm bg-amber-100 text-amber-900",
+        }
+    }
+}
+
+/// A field of an entry and its value at each block, missing where the entry does not exist
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub struct FieldChange {
+    pub field: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl FieldChange {
+    pub fn changed(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+/// An account, program or validator which was added, removed or modified, with all its fields
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub struct EntryChange {
+    pub kind: ChangeKind,
+    /// Account ID, program hash or validator stash account
+    pub key: String,
+    pub fields: Vec<FieldChange>,
+}
+
+/// What changed in chain state between two blocks
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub struct StateDiff {
+    pub from: BlockRef,
+    pub to: BlockRef,
+    pub accounts: Vec<EntryChange>,
+    pub programs: Vec<EntryChange>,
+    pub validators: Vec<EntryChange>,
+}
+
+impl StateDiff {
+    pub fn between(from: &Snapshot, to: &Snapshot) -> Self {
+        StateDiff {
+            from: from.block.clone(),
+            to: to.block.clone(),
+            accounts: compare(&from.accounts, &to.accounts),
+            programs: compare(&from.programs, &to.programs),
+            validators: compare(&from.validators, &to.validators),
+        }
+    }
+}
+
+/// Something in a snapshot which can be compared between blocks
+trait Compare {
+    /// Identifies the same entry at both blocks
+    fn key(&self) -> String;
+    /// Everything else, displayed so that it can be compared
+    fn fields(&self) -> Vec<(&'static str, String)>;
+}
+
+impl Compare for RegisteredAccount {
+    fn key(&self) -> String {
+        self.account_id.to_string()
+    }
+
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("key_visibility", self.key_visibility.0.clone()),
+            ("program_modification_account", self.program_modification_account.clone()),
+            ("verifying_key", format!("{:?}", self.verifying_key)),
+            (
+                "ethereum_address",
+                self.ethereum_address.map(|address| format!("{:?}", address)).unwrap_or_default(),
+            ),
+            ("program_pointers", self.program_pointers.join(", ")),
+        ]
+    }
+}
+
+impl Compare for Program {
+    fn key(&self) -> String {
+        self.hash.clone()
+    }
+
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("deployer", self.deployer.clone()),
+            ("ref_counter", self.ref_counter.to_string()),
+            ("size", self.size.to_string()),
+            ("configurable", self.configurable.to_string()),
+        ]
+    }
+}
+
+impl Compare for Validator {
+    fn key(&self) -> String {
+        self.stash_account.to_string()
+    }
+
+    // Probe results are not chain state, so are left out
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("tss_account", self.tss_account.to_string()),
+            ("x25519_public_key", format!("{:?}", self.x25519_public_key)),
+            ("endpoint", self.endpoint.clone()),
+        ]
+    }
+}
+
+/// The entries which differ between two blocks, ordered by key
+fn compare<T: Compare>(from: &[T], to: &[T]) -> Vec<EntryChange> {
+    let from: BTreeMap<String, &T> = from.iter().map(|entry| (entry.key(), entry)).collect();
+    let to: BTreeMap<String, &T> = to.iter().map(|entry| (entry.key(), entry)).collect();
+    let mut keys: Vec<&String> = from.keys().chain(to.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let before = from.get(key).map(|entry| entry.fields());
+            let after = to.get(key).map(|entry| entry.fields());
+            let kind = match (&before, &after) {
+                (None, Some(_)) => ChangeKind::Added,
+                (Some(_), None) => ChangeKind::Removed,
+                (Some(before), Some(after)) if before != after => ChangeKind::Modified,
+                _ => return None,
+            };
+            let names = before.as_ref().or(after.as_ref())?.iter().map(|(name, _)| *name);
+            let value = |fields: &Option<Vec<(&'static str, String)>>, index: usize| {
+                fields.as_ref().map(|fields| fields[index].1.clone())
+            };
+            let fields = names
+                .enumerate()
+                .map(|(index, name)| FieldChange {
+                    field: name.to_string(),
+                    from: value(&before, index),
+                    to: value(&after, index),
+                })
+                .collect();
+            Some(EntryChange {
+                kind,
+                key: key.clone(),
+                fields,
+            })
+        })
+        .collect()
+}
+
+/// Compare chain state at block `from` with block `to`, or with the latest indexed block
+#[server(GetStateDiff, "/api")]
+pub async fn get_state_diff(network: String, from: String, to: Option<String>) -> Result<StateDiff, ServerFnError> {
+    let from = crate::snapshot_at(&network, Some(from)).await?;
+    let to = crate::snapshot_at(&network, to).await?;
+    Ok(StateDiff::between(&from, &to))
+}
+
+/// A query parameter, trimmed, treating an empty one as missing
+fn use_query_param(name: &'static str) -> Memo<Option<String>> {
+    let query = use_query_map();
+    create_memo(move |_| {
+        query.with(|query| query.get(name).map(|value| value.trim().to_string()).filter(|value| !value.is_empty()))
+    })
+}
+
+/// Page comparing two blocks given as `?from=&to=`
+#[component]
+pub fn DiffPage() -> impl IntoView {
+    let network = use_network();
+    let from = use_query_param("from");
+    let to = use_query_param("to");
+    let diff = create_resource(
+        move || (network.get(), from.get(), to.get()),
+        |(network, from, to)| async move {
+            match from {
+                Some(from) => Some(get_state_diff(network, from, to).await),
+                None => None,
+            }
+        },
+    );
+    view! {
+        <div class="container mx-auto">
+            <A href=network_href("") class="text-sm underline">
+                "Back to status page"
+            </A>
+            <h1 class="text-2xl my-4">"Compare Blocks"</h1>
+            <Form method="GET" action="" class="flex items-center gap-2 text-sm text-blue-gray-900">
+                <label for="from">"From block"</label>
+                <input
+                    id="from"
+                    name="from"
+                    class="px-2 py-1 rounded-md border border-blue-gray-100 w-48"
+                    placeholder="Number or 0x hash"
+                    prop:value=move || from.get().unwrap_or_default()
+                />
+                <label for="to">"to block"</label>
+                <input
+                    id="to"
+                    name="to"
+                    class="px-2 py-1 rounded-md border border-blue-gray-100 w-48"
+                    placeholder="Latest"
+                    prop:value=move || to.get().unwrap_or_default()
+                />
+                <button type="submit" class="px-2 py-1 rounded-md bg-blue-50">
+                    "Compare"
+                </button>
+            </Form>
+            <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                {move || {
+                    diff.get()
+                        .flatten()
+                        .map(|diff| match diff {
+                            Err(e) => {
+                                view! { <pre class="error">"server error: " {e.to_string()}</pre> }
+                                    .into_view()
+                            }
+                            Ok(diff) => view! { <StateDiffView diff/> }.into_view(),
+                        })
+                        .unwrap_or_default()
+                }}
+
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+fn StateDiffView(diff: StateDiff) -> impl IntoView {
+    let StateDiff { from, to, accounts, programs, validators } = diff;
+    view! {
+        <p class="my-4 text-sm text-blue-gray-900">
+            <span title=from.hash.clone()>{format!("From block #{}", from.number)}</span>
+            " "
+            <span title=to.hash.clone()>{format!("to block #{}", to.number)}</span>
+        </p>
+        <EntryChanges title="Registered Entropy Accounts" path="account" changes=accounts from=from.clone() to=to.clone()/>
+        <EntryChanges title="Programs" path="program" changes=programs from=from.clone() to=to.clone()/>
+        <EntryChanges title="Validators" path="validator" changes=validators from to/>
+    }
+}
+
+/// A table of the changed entries of one kind, linking each to its page at the block it exists at
+#[component]
+fn EntryChanges(
+    title: &'static str,
+    /// Path of the entry's detail page within the network
+    path: &'static str,
+    changes: Vec<EntryChange>,
+    from: BlockRef,
+    to: BlockRef,
+) -> impl IntoView {
+    let rows = if changes.is_empty() {
+        view! {
+            <tr>
+                <td class="p-4">"No changes."</td>
+            </tr>
+        }
+            .into_view()
+    } else {
+        changes
+            .into_iter()
+            .map(|change| {
+                let block = if change.kind == ChangeKind::Removed { &from } else { &to };
+                let href = network_href(&format!("/{}/{}?block={}", path, change.key, block.hash));
+                view! {
+                    <tr class="hover:bg-gray-200 align-top">
+                        <td class="p-4">
+                            <span class=change.kind.class()>{change.kind.label()}</span>
+                        </td>
+                        <td class="p-4 text-sm">
+                            <A href=href class="underline break-all">
+                                <code>{change.key}</code>
+                            </A>
+                        </td>
+                        <td class="p-4 text-sm">
+                            {change
+                                .fields
+                                .into_iter()
+                                .map(|field| view! { <FieldValues field/> })
+                                .collect_view()}
+                        </td>
+                    </tr>
+                }
+            })
+            .collect_view()
+    };
+    view! {
+        <DetailsTable title headings=vec!["Change", "Key", "Fields"]>
+            {rows}
+        </DetailsTable>
+    }
+}
+
+/// A field's old and new values, highlighted if they differ
+#[component]
+fn FieldValues(field: FieldChange) -> impl IntoView {
+    let changed = field.changed();
+    let FieldChange { field, from, to } = field;
+    let values = match (from, to) {
+        (Some(from), Some(to)) if changed => view! {
+            <code class="bg-red-50 text-red-900 line-through break-all">{from}</code>
+            " → "
+            <code class="bg-green-50 text-green-900 break-all">{to}</code>
+        }
+            .into_view(),
+        (Some(value), None) => view! { <code class="bg-red-50 text-red-900 break-all">{value}</code> }.into_view(),
+        (None, Some(value)) => view! { <code class="bg-green-50 text-green-900 break-all">{value}</code> }.into_view(),
+        (Some(value), _) => view! { <code class="break-all">{value}</code> }.into_view(),
+        (None, None) => ().into_view(),
+    };
+    view! {
+        <p class="text-blue-gray-900" class:font-semibold=changed>
+            <span class="opacity-70">{field} ": "</span>
+            {values}
+        </p>
+    }
+}
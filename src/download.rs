@@ -0,0 +1,151 @@
+/// Route serving the raw bytecode of a stored program
+pub const BYTECODE_ROUTE: &str = "/api/program/:hash/bytecode.wasm";
+
+/// Route serving the raw configuration interface of a stored program
+pub const CONFIGURATION_INTERFACE_ROUTE: &str = "/api/program/:hash/configuration-interface";
+
+/// Download link for the bytecode stored under `hash` at the given block
+pub fn bytecode_url(network: &str, hash: &str, block_hash: &str) -> String {
+    format!("{}?network={}&block={}", BYTECODE_ROUTE.replace(":hash", hash), network, block_hash)
+}
+
+/// Download link for the configuration interface stored under `hash` at the given block
+pub fn configuration_interface_url(network: &str, hash: &str, block_hash: &str) -> String {
+    format!(
+        "{}?network={}&block={}",
+        CONFIGURATION_INTERFACE_ROUTE.replace(":hash", hash),
+        network,
+        block_hash
+    )
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::{
+            indexer::{parse_hash, UnknownBlock},
+            program::fetch_program_info,
+            state::AppState,
+        };
+        use axum::{
+            extract::{Path, Query, State},
+            http::{header, HeaderMap, HeaderValue, StatusCode},
+            response::{IntoResponse, Response},
+        };
+        use entropy_testing_utils::chain_api::entropy::runtime_types::pallet_programs::pallet::ProgramInfo;
+        use serde::Deserialize;
+        use subxt::{ext::sp_core::hashing::blake2_256, utils::{AccountId32, H256}};
+
+        /// Which network to read the program from, and at which block number or hash. The latest
+        /// indexed block of the default network is used when not given.
+        #[derive(Deserialize)]
+        pub struct DownloadQuery {
+            pub network: Option<String>,
+            pub block: Option<String>,
+        }
+
+        /// Download the bytecode of a stored program
+        pub async fn program_bytecode(
+            State(state): State<AppState>,
+            Path(hash): Path<String>,
+            Query(query): Query<DownloadQuery>,
+        ) -> Result<Response, (StatusCode, String)> {
+            let (program_hash, program_info) = get_program_info(&state, query, &hash).await?;
+            let verified = hash_matches(program_hash, &program_info);
+            let headers = download_headers(
+                "application/wasm",
+                &filename(program_hash, verified, "wasm"),
+                verified,
+            )?;
+            Ok((headers, program_info.bytecode).into_response())
+        }
+
+        /// Download the configuration interface of a stored program
+        pub async fn program_configuration_interface(
+            State(state): State<AppState>,
+            Path(hash): Path<String>,
+            Query(query): Query<DownloadQuery>,
+        ) -> Result<Response, (StatusCode, String)> {
+            let (program_hash, program_info) = get_program_info(&state, query, &hash).await?;
+            if program_info.configuration_interface.is_empty() {
+                return Err((StatusCode::NOT_FOUND, "Program has no configuration interface".to_string()));
+            }
+            let verified = hash_matches(program_hash, &program_info);
+            let (content_type, extension) =
+                match serde_json::from_slice::<serde_json::Value>(&program_info.configuration_interface) {
+                    Ok(_) => ("application/json", "json"),
+                    Err(_) => ("application/octet-stream", "bin"),
+                };
+            let headers = download_headers(
+                content_type,
+                &filename(program_hash, verified, &format!("configuration-interface.{}", extension)),
+                verified,
+            )?;
+            Ok((headers, program_info.configuration_interface).into_response())
+        }
+
+        async fn get_program_info(
+            state: &AppState,
+            query: DownloadQuery,
+            hash: &str,
+        ) -> Result<(H256, ProgramInfo<AccountId32>), (StatusCode, String)> {
+            let program_hash = parse_hash(hash).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+            let network = state.networks.resolve(query.network.as_deref())?;
+            let block = network.block_ref(query.block.as_deref()).await.map_err(|e| {
+                let status = if e.is::<UnknownBlock>() { StatusCode::BAD_REQUEST } else { StatusCode::SERVICE_UNAVAILABLE };
+                (status, e.to_string())
+            })?;
+            let (api, _rpc) = network
+                .chain
+                .get()
+                .await
+                .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e.to_string()))?;
+            let block_hash = block.block_hash().map_err(internal_error)?;
+            let program_info = fetch_program_info(&api, block_hash, program_hash)
+                .await
+                .map_err(internal_error)?
+                .ok_or_else(|| (StatusCode::NOT_FOUND, "No program is stored with this hash".to_string()))?;
+            Ok((program_hash, program_info))
+        }
+
+        /// Whether the stored program hashes to the hash it is stored under. Depending on the
+        /// runtime version, the hash covers either the bytecode alone or the bytecode followed by
+        /// the configuration interface.
+        fn hash_matches(program_hash: H256, program_info: &ProgramInfo<AccountId32>) -> bool {
+            let with_interface = [
+                program_info.bytecode.as_slice(),
+                program_info.configuration_interface.as_slice(),
+            ]
+            .concat();
+            blake2_256(&program_info.bytecode) == program_hash.0 || blake2_256(&with_interface) == program_hash.0
+        }
+
+        /// Files are named after the program hash, marked if the content did not match it
+        fn filename(program_hash: H256, verified: bool, extension: &str) -> String {
+            let hash = hex::encode(program_hash.0);
+            if verified {
+                format!("{}.{}", hash, extension)
+            } else {
+                format!("{}-unverified.{}", hash, extension)
+            }
+        }
+
+        fn download_headers(
+            content_type: &'static str,
+            filename: &str,
+            verified: bool,
+        ) -> Result<HeaderMap, (StatusCode, String)> {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+            headers.insert(
+                header::CONTENT_DISPOSITION,
+                HeaderValue::from_str(&format!("attachment; filename=\"{}\"", filename)).map_err(internal_error)?,
+            );
+            headers.insert("x-hash-verified", HeaderValue::from_static(if verified { "true" } else { "false" }));
+            Ok(headers)
+        }
+
+        fn internal_error(error: impl ToString) -> (StatusCode, String) {
+            (StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use crate::{display_timestamp, network::use_network};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+/// How far back the history charts look
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HistoryRange {
+    Day,
+    Week,
+    Month,
+}
+
+impl HistoryRange {
+    pub const ALL: [HistoryRange; 3] = [HistoryRange::Day, HistoryRange::Week, HistoryRange::Month];
+
+    pub fn seconds(&self) -> u64 {
+        match self {
+            HistoryRange::Day => 24 * 60 * 60,
+            HistoryRange::Week => 7 * 24 * 60 * 60,
+            HistoryRange::Month => 30 * 24 * 60 * 60,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HistoryRange::Day => "Day",
+            HistoryRange::Week => "Week",
+            HistoryRange::Month => "Month",
+        }
+    }
+}
+
+/// Network status over one interval of a history chart
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryPoint {
+    /// Unix time in seconds of the last sample in the interval
+    pub time: u64,
+    /// The counts are missing when nothing had been indexed yet
+    pub block: Option<u32>,
+    pub accounts: Option<u64>,
+    pub programs: Option<u64>,
+    pub validators: Option<u64>,
+    /// Fraction of samples in which the chain endpoint could be reached
+    pub chain_availability: f64,
+    /// Fraction of threshold server checks which succeeded, if any were made
+    pub tss_availability: Option<f64>,
+}
+
+#[server(GetHistory, "/api")]
+pub async fn get_history(network: String, range: HistoryRange) -> Result<Vec<HistoryPoint>, ServerFnError> {
+    crate::history_store(&network)?
+        .points(range)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Charts of growth and availability, with a choice of time range
+#[component]
+pub fn HistoryCharts() -> impl IntoView {
+    let network = use_network();
+    let (range, set_range) = create_signal(HistoryRange::Day);
+    let history = create_resource(
+        move || (network.get(), range.get()),
+        |(network, range)| get_history(network, range),
+    );
+    view! {
+        <div class="flex items-center justify-between">
+            <h2 class="my-4 block font-sans text-xl antialiased leading-snug tracking-normal mt-4 text-gray-700">
+                "History"
+            </h2>
+            <div class="flex gap-2">
+                {HistoryRange::ALL
+                    .into_iter()
+                    .map(|option| {
+                        view! {
+                            <button
+                                class="px-2 py-1 text-sm rounded-md"
+                                class:bg-blue-50=move || range.get() == option
+                                on:click=move |_| set_range.set(option)
+                            >
+                                {option.label()}
+                            </button>
+                        }
+                    })
+                    .collect_view()}
+            </div>
+        </div>
+        <Transition fallback=move || view! { <p>"Loading..."</p> }>
+            {move || {
+                history
+                    .get()
+                    .map(|history| match history {
+                        Err(e) => {
+                            view! { <p class="text-sm text-gray-500" title=e.to_string()>"Unavailable"</p> }
+                                .into_view()
+                        }
+                        Ok(points) => {
+                            let series = |value: fn(&HistoryPoint) -> Option<f64>| {
+                                points
+                                    .iter()
+                                    .filter_map(|point| value(point).map(|value| (point.time, value)))
+                                    .collect::<Vec<_>>()
+                            };
+                            view! {
+                                <div class="grid grid-cols-1 md:grid-cols-3 gap-4">
+                                    <LineChart
+                                        title="Registered Accounts"
+                                        points=series(|point| point.accounts.map(|count| count as f64))
+                                        display=display_count
+                                    />
+                                    <LineChart
+                                        title="Programs"
+                                        points=series(|point| point.programs.map(|count| count as f64))
+                                        display=display_count
+                                    />
+                                    <LineChart
+                                        title="Validators"
+                                        points=series(|point| point.validators.map(|count| count as f64))
+                                        display=display_count
+                                    />
+                                    <LineChart
+                                        title="Chain Availability"
+                                        points=series(|point| Some(point.chain_availability))
+                                        display=display_percentage
+                                    />
+                                    <LineChart
+                                        title="Threshold Server Availability"
+                                        points=series(|point| point.tss_availability)
+                                        display=display_percentage
+                                    />
+                                </div>
+                            }
+                                .into_view()
+                        }
+                    })
+                    .unwrap_or_default()
+            }}
+
+        </Transition>
+    }
+}
+
+fn display_count(value: f64) -> String {
+    format!("{:.0}", value)
+}
+
+fn display_percentage(value: f64) -> String {
+    format!("{:.1}%", value * 100.0)
+}
+
+/// A simple SVG line chart of values over time
+#[component]
+pub fn LineChart(title: &'static str, points: Vec<(u64, f64)>, display: fn(f64) -> String) -> impl IntoView {
+    let chart = match (points.first(), points.last()) {
+        (Some(first), Some(last)) if points.len() > 1 => {
+            let (start, end) = (first.0, last.0);
+            let min = points.iter().map(|(_, value)| *value).fold(f64::INFINITY, f64::min);
+            let max = points.iter().map(|(_, value)| *value).fold(f64::NEG_INFINITY, f64::max);
+            // Draw a flat line across the middle of the chart
+            let (bottom, span) = if max > min { (min, max - min) } else { (min - 0.5, 1.0) };
+            let polyline = points
+                .iter()
+                .map(|(time, value)| {
+                    let x = (time - start) as f64 / (end - start).max(1) as f64 * 300.0;
+                    let y = 95.0 - (value - bottom) / span * 90.0;
+                    format!("{:.1},{:.1}", x, y)
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            view! {
+                <svg viewBox="0 0 300 100" preserveAspectRatio="none" class="w-full h-24">
+                    <polyline
+                        points=polyline
+                        fill="none"
+                        stroke="currentColor"
+                        stroke-width="1.5"
+                        vector-effect="non-scaling-stroke"
+                    ></polyline>
+                </svg>
+                <div class="flex justify-between text-xs text-gray-500">
+                    <span>{display_timestamp(start)}</span>
+                    <span>{format!("min {} / max {}", display(min), display(max))}</span>
+                    <span>{display_timestamp(end)}</span>
+                </div>
+            }
+                .into_view()
+        }
+        _ => view! { <p class="text-sm text-gray-500">"Not enough data yet"</p> }.into_view(),
+    };
+    let latest = points.last().map(|(_, value)| display(*value)).unwrap_or_default();
+    view! {
+        <div class="p-4 text-blue-gray-900 bg-white shadow-md rounded-xl bg-clip-border">
+            <div class="flex justify-between">
+                <p class="block font-sans text-sm antialiased font-normal leading-none opacity-70">{title}</p>
+                <p class="block font-sans text-sm antialiased font-normal leading-none">{latest}</p>
+            </div>
+            {chart}
+        </div>
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::{
+            chain::{unix_now, ChainClient},
+            database::Database,
+            indexer::SnapshotCache,
+            probe::ProbeCache,
+        };
+        use rusqlite::params;
+        use std::time::Duration;
+
+        /// Most points returned for a chart, so that long ranges are averaged into intervals
+        const MAX_POINTS: u64 = 200;
+
+        const SCHEMA: &str = "
+            CREATE TABLE IF NOT EXISTS snapshots (
+                network TEXT NOT NULL,
+                time INTEGER NOT NULL,
+                block INTEGER,
+                accounts INTEGER,
+                programs INTEGER,
+                validators INTEGER,
+                chain_up INTEGER NOT NULL,
+                validators_probed INTEGER NOT NULL,
+                validators_up INTEGER NOT NULL,
+                PRIMARY KEY (network, time)
+            );
+            CREATE TABLE IF NOT EXISTS probes (
+                network TEXT NOT NULL,
+                time INTEGER NOT NULL,
+                stash_account TEXT NOT NULL,
+                endpoint TEXT NOT NULL,
+                reachable INTEGER NOT NULL,
+                latency_ms INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS probes_network_time ON probes (network, time);
+        ";
+
+        /// Periodic samples of the status of one network, kept in the local database
+        #[derive(Clone)]
+        pub struct HistoryStore {
+            database: Database,
+            network: String,
+            retention: Duration,
+        }
+
+        impl HistoryStore {
+            pub fn open(database: Database, network: &str, retention: Duration) -> anyhow::Result<Self> {
+                database.create_tables(SCHEMA)?;
+                Ok(HistoryStore {
+                    database,
+                    network: network.to_string(),
+                    retention,
+                })
+            }
+
+            pub fn database(&self) -> &Database {
+                &self.database
+            }
+
+            /// Name of the network whose samples this store holds
+            pub fn network(&self) -> &str {
+                &self.network
+            }
+
+            /// Record the current status of the chain, indexed state and threshold servers. Nothing
+            /// is recorded until the chain client has tried to connect, as until then it is not
+            /// known whether the chain is up.
+            async fn record(&self, chain: &ChainClient, snapshots: &SnapshotCache, probes: &ProbeCache) -> anyhow::Result<()> {
+                let status = chain.status();
+                if status.is_connecting() {
+                    return Ok(());
+                }
+                let time = unix_now();
+                let chain_up = status.is_reachable();
+                let snapshot = snapshots.get();
+                let counts = snapshot.as_ref().map(|snapshot| {
+                    (
+                        snapshot.block.number,
+                        snapshot.accounts.len() as u64,
+                        snapshot.programs.len() as u64,
+                        snapshot.validators.len() as u64,
+                    )
+                });
+                let probe_rows = snapshot
+                    .iter()
+                    .flat_map(|snapshot| snapshot.validators.iter())
+                    .filter_map(|validator| {
+                        probes.get(validator).map(|probe| {
+                            (
+                                validator.stash_account.to_string(),
+                                validator.endpoint.clone(),
+                                probe.reachable,
+                                probe.latency_ms,
+                            )
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                let network = self.network.clone();
+                self.database.with_connection(move |connection| {
+                    let transaction = connection.unchecked_transaction()?;
+                    let validators_up = probe_rows.iter().filter(|(_, _, reachable, _)| *reachable).count();
+                    transaction.execute(
+                        "INSERT OR REPLACE INTO snapshots VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                        params![
+                            network,
+                            time,
+                            counts.map(|counts| counts.0),
+                            counts.map(|counts| counts.1),
+                            counts.map(|counts| counts.2),
+                            counts.map(|counts| counts.3),
+                            chain_up,
+                            probe_rows.len(),
+                            validators_up,
+                        ],
+                    )?;
+                    for (stash_account, endpoint, reachable, latency_ms) in &probe_rows {
+                        transaction.execute(
+                            "INSERT INTO probes VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                            params![network, time, stash_account, endpoint, reachable, latency_ms],
+                        )?;
+                    }
+                    transaction.commit()
+                })
+                .await
+            }
+
+            /// Delete samples older than the retention period
+            async fn prune(&self) -> anyhow::Result<()> {
+                let cutoff = unix_now().saturating_sub(self.retention.as_secs());
+                let network = self.network.clone();
+                self.database.with_connection(move |connection| {
+                    connection.execute("DELETE FROM snapshots WHERE network = ?1 AND time < ?2", params![network, cutoff])?;
+                    connection.execute("DELETE FROM probes WHERE network = ?1 AND time < ?2", params![network, cutoff])?;
+                    Ok(())
+                })
+                .await
+            }
+
+            /// Samples within the given range, averaged into at most `MAX_POINTS` intervals
+            pub async fn points(&self, range: HistoryRange) -> anyhow::Result<Vec<HistoryPoint>> {
+                let since = unix_now().saturating_sub(range.seconds());
+                let interval = (range.seconds() / MAX_POINTS).max(1);
+                let network = self.network.clone();
+                self.database.with_connection(move |connection| {
+                    let mut statement = connection.prepare(
+                        "SELECT MAX(time), MAX(block), MAX(accounts), MAX(programs), MAX(validators),
+                            AVG(chain_up), CAST(SUM(validators_up) AS REAL) / NULLIF(SUM(validators_probed), 0)
+                        FROM snapshots WHERE network = ?1 AND time >= ?2 GROUP BY time / ?3 ORDER BY 1",
+                    )?;
+                    let points = statement.query_map(params![network, since, interval], |row| {
+                        Ok(HistoryPoint {
+                            time: row.get(0)?,
+                            block: row.get(1)?,
+                            accounts: row.get(2)?,
+                            programs: row.get(3)?,
+                            validators: row.get(4)?,
+                            chain_availability: row.get(5)?,
+                            tss_availability: row.get(6)?,
+                        })
+                    })?;
+                    points.collect()
+                })
+                .await
+            }
+        }
+
+        /// Record network status every `interval`, deleting old samples.
+        /// Runs forever, so should be spawned.
+        pub async fn run_recorder(
+            store: HistoryStore,
+            chain: ChainClient,
+            snapshots: SnapshotCache,
+            probes: ProbeCache,
+            interval: Duration,
+        ) {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(error) = store.record(&chain, &snapshots, &probes).await {
+                    log::warn!("Failed to record network status history: {}", error);
+                }
+                if let Err(error) = store.prune().await {
+                    log::warn!("Failed to delete old network status history: {}", error);
+                }
+            }
+        }
+    }
+}
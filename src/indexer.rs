@@ -0,0 +1,386 @@
+use crate::{
+    program::Program,
+    registered_account::RegisteredAccount,
+    registration::{PendingRegistration, RegistrationOutcome},
+    validator::Validator,
+};
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+/// The block at which a snapshot was read
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub struct BlockRef {
+    pub number: u32,
+    pub hash: String,
+}
+
+/// A consistent view of chain state, all read at the same finalized block
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub block: BlockRef,
+    pub accounts: Vec<RegisteredAccount>,
+    pub programs: Vec<Program>,
+    pub validators: Vec<Validator>,
+    /// Accounts waiting for their registration to be confirmed
+    pub pending_registrations: Vec<PendingRegistration>,
+    /// Registrations which recently ended, newest first. Only known for the latest block.
+    pub recent_registrations: Vec<RegistrationOutcome>,
+}
+
+/// Chain state at the given block number or hash, or at the latest indexed block
+#[server(GetSnapshot, "/api")]
+pub async fn get_snapshot(network: String, block: Option<String>) -> Result<Snapshot, ServerFnError> {
+    Ok(crate::snapshot_at(&network, block).await?.as_ref().clone())
+}
+
+/// The block number or hash chosen with the `?block=` query parameter, if any
+pub fn use_block() -> Memo<Option<String>> {
+    let query = use_query_map();
+    create_memo(move |_| {
+        query.with(|query| query.get("block").map(|block| block.trim().to_string()).filter(|block| !block.is_empty()))
+    })
+}
+
+/// Add the chosen block, if any, to a link so that the linked page shows the same block
+pub fn with_block(href: String) -> String {
+    match use_query_map().with_untracked(|query| query.get("block").cloned()) {
+        Some(block) if !block.trim().is_empty() => format!("{}?block={}", href, encode_query_value(block.trim())),
+        _ => href,
+    }
+}
+
+/// Percent-encode everything but unreserved characters, for use in a query string
+fn encode_query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// A form for viewing the current page as of an earlier block
+#[component]
+pub fn BlockPicker() -> impl IntoView {
+    let block = use_block();
+    view! {
+        <Form method="GET" action="" class="flex items-center gap-2 text-sm text-blue-gray-900">
+            <label for="block">"View at block"</label>
+            <input
+                id="block"
+                name="block"
+                class="px-2 py-1 rounded-md border border-blue-gray-100 w-48"
+                placeholder="Number or 0x hash"
+                prop:value=move || block.get().unwrap_or_default()
+            />
+            <button type="submit" class="px-2 py-1 rounded-md bg-blue-50">
+                "Go"
+            </button>
+        </Form>
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::{
+            blocks::block_time,
+            chain::{unix_now, ChainClient},
+            probe::ProbeCache,
+            program::query_programs,
+            registered_account::query_accounts,
+            registration::{query_pending_registrations, RegistrationTracker},
+            signing::SigningMonitor,
+            validator::query_validators,
+        };
+        use anyhow::anyhow;
+        use entropy_testing_utils::chain_api::EntropyConfig;
+        use futures::StreamExt;
+        use std::{
+            collections::VecDeque,
+            sync::{Arc, Mutex, RwLock},
+            time::{Duration, Instant},
+        };
+        use subxt::{backend::legacy::LegacyRpcMethods, blocks::Block, utils::H256, OnlineClient};
+        use tokio::sync::broadcast;
+
+        /// How long to wait before resubscribing after the block subscription fails
+        const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+        /// How many snapshots can be queued for a slow subscriber before it starts missing them
+        const UPDATE_CHANNEL_CAPACITY: usize = 16;
+
+        /// Most blocks finalized while resubscribing whose events are read to catch up
+        const MAX_CATCH_UP_BLOCKS: u32 = 100;
+
+        /// How many snapshots of earlier blocks are kept, across all networks
+        const HISTORIC_SNAPSHOTS: usize = 32;
+
+        /// The most recent snapshot, shared between the indexer and server functions, with a
+        /// channel announcing each new one
+        #[derive(Clone)]
+        pub struct SnapshotCache {
+            current: Arc<RwLock<Option<Arc<Snapshot>>>>,
+            last_updated: Arc<RwLock<Option<u64>>>,
+            updates: broadcast::Sender<Arc<Snapshot>>,
+        }
+
+        impl Default for SnapshotCache {
+            fn default() -> Self {
+                let (updates, _) = broadcast::channel(UPDATE_CHANNEL_CAPACITY);
+                SnapshotCache {
+                    current: Default::default(),
+                    last_updated: Default::default(),
+                    updates,
+                }
+            }
+        }
+
+        impl SnapshotCache {
+            pub fn get(&self) -> Option<Arc<Snapshot>> {
+                self.current.read().ok().and_then(|snapshot| snapshot.clone())
+            }
+
+            /// Unix time in seconds at which the latest snapshot was indexed
+            pub fn last_updated(&self) -> Option<u64> {
+                self.last_updated.read().ok().and_then(|time| *time)
+            }
+
+            /// Receive each new snapshot as it is indexed
+            pub fn subscribe(&self) -> broadcast::Receiver<Arc<Snapshot>> {
+                self.updates.subscribe()
+            }
+
+            fn set(&self, snapshot: Snapshot) {
+                let snapshot = Arc::new(snapshot);
+                if let Ok(mut current) = self.current.write() {
+                    *current = Some(snapshot.clone());
+                }
+                if let Ok(mut last_updated) = self.last_updated.write() {
+                    *last_updated = Some(unix_now());
+                }
+                // An error here only means nobody is listening
+                let _ = self.updates.send(snapshot);
+            }
+        }
+
+        /// Snapshots of earlier blocks which have been read on demand, so that going back and
+        /// forth between pages at the same block does not read it again. Shared by all networks
+        /// and keyed by network name and block hash, evicting the least recently used.
+        #[derive(Clone, Default)]
+        pub struct HistoricSnapshots(Arc<Mutex<VecDeque<((String, String), Arc<Snapshot>)>>>);
+
+        impl HistoricSnapshots {
+            pub fn get(&self, network: &str, block_hash: &str) -> Option<Arc<Snapshot>> {
+                let mut snapshots = self.0.lock().ok()?;
+                let index = snapshots
+                    .iter()
+                    .position(|((name, hash), _)| name == network && hash == block_hash)?;
+                let entry = snapshots.remove(index)?;
+                let snapshot = entry.1.clone();
+                snapshots.push_front(entry);
+                Some(snapshot)
+            }
+
+            pub fn insert(&self, network: &str, snapshot: Arc<Snapshot>) {
+                if let Ok(mut snapshots) = self.0.lock() {
+                    let key = (network.to_string(), snapshot.block.hash.clone());
+                    snapshots.retain(|(existing, _)| existing != &key);
+                    snapshots.push_front((key, snapshot));
+                    snapshots.truncate(HISTORIC_SNAPSHOTS);
+                }
+            }
+        }
+
+        impl BlockRef {
+            /// The block hash, for making storage queries
+            pub fn block_hash(&self) -> anyhow::Result<H256> {
+                parse_hash(&self.hash)
+            }
+        }
+
+        /// Parse a hex encoded block or program hash, with or without the `0x` prefix
+        pub fn parse_hash(hash: &str) -> anyhow::Result<H256> {
+            let bytes: [u8; 32] = hex::decode(hash.trim_start_matches("0x"))?
+                .try_into()
+                .map_err(|_| anyhow!("Hash should be 32 bytes"))?;
+            Ok(H256(bytes))
+        }
+
+        /// A block which was asked for but does not exist, as opposed to one which could not be
+        /// read
+        #[derive(Debug, thiserror::Error)]
+        #[error("{0}")]
+        pub struct UnknownBlock(String);
+
+        /// Find a block given its number, or its hash starting with `0x`
+        pub async fn find_block(rpc: &LegacyRpcMethods<EntropyConfig>, block: &str) -> anyhow::Result<BlockRef> {
+            if block.starts_with("0x") {
+                let hash = parse_hash(block).map_err(|e| UnknownBlock(e.to_string()))?;
+                let header = rpc
+                    .chain_get_header(Some(hash))
+                    .await?
+                    .ok_or_else(|| UnknownBlock(format!("No block with hash {}", block)))?;
+                Ok(BlockRef {
+                    number: header.number,
+                    hash: format!("{:?}", hash),
+                })
+            } else {
+                let number: u32 = block.parse().map_err(|_| {
+                    UnknownBlock(format!("A block should be given as a number or a 0x prefixed hash, not {}", block))
+                })?;
+                let hash = rpc
+                    .chain_get_block_hash(Some(number.into()))
+                    .await?
+                    .ok_or_else(|| UnknownBlock(format!("No block #{}", number)))?;
+                Ok(BlockRef {
+                    number,
+                    hash: format!("{:?}", hash),
+                })
+            }
+        }
+
+        /// Follow finalized blocks, refreshing the snapshot cache on each one, and recording
+        /// signature events if given a signing monitor. Runs forever, so should be spawned.
+        pub async fn run_indexer(
+            chain: ChainClient,
+            cache: SnapshotCache,
+            probes: ProbeCache,
+            signing: Option<SigningMonitor>,
+        ) {
+            let mut registrations = RegistrationTracker::default();
+            let mut last_observed = None;
+            loop {
+                let followed = follow_finalized_blocks(
+                    &chain,
+                    &cache,
+                    &probes,
+                    &mut registrations,
+                    signing.as_ref(),
+                    &mut last_observed,
+                )
+                .await;
+                if let Err(error) = followed {
+                    log::warn!("Indexer lost block subscription: {}", error);
+                    chain.reset().await;
+                }
+                tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+            }
+        }
+
+        async fn follow_finalized_blocks(
+            chain: &ChainClient,
+            cache: &SnapshotCache,
+            probes: &ProbeCache,
+            registrations: &mut RegistrationTracker,
+            signing: Option<&SigningMonitor>,
+            last_observed: &mut Option<u32>,
+        ) -> anyhow::Result<()> {
+            let (api, rpc) = chain.get().await?;
+            if let (Some(signing), None) = (signing, *last_observed) {
+                signing.check_events(&api.metadata());
+            }
+            let mut blocks = api.blocks().subscribe_finalized().await?;
+            while let Some(block) = blocks.next().await {
+                let block = block?;
+                // Blocks finalized while resubscribing are not part of the new subscription, but
+                // their events are still needed
+                if let Some(last) = *last_observed {
+                    let first_missed = (last + 1).max(block.number().saturating_sub(MAX_CATCH_UP_BLOCKS));
+                    if first_missed > last + 1 {
+                        log::warn!("Skipping events of blocks #{} to #{}", last + 1, first_missed - 1);
+                    }
+                    for number in first_missed..block.number() {
+                        let hash = rpc
+                            .chain_get_block_hash(Some(number.into()))
+                            .await?
+                            .ok_or_else(|| anyhow!("No block #{}", number))?;
+                        observe_events(registrations, signing, cache, &api.blocks().at(hash).await?).await;
+                        *last_observed = Some(number);
+                    }
+                }
+                observe_events(registrations, signing, cache, &block).await;
+                *last_observed = Some(block.number());
+
+                let block_ref = BlockRef {
+                    number: block.number(),
+                    hash: format!("{:?}", block.hash()),
+                };
+                let started = Instant::now();
+                let snapshot = read_snapshot(&api, block.hash(), block_ref).await;
+                chain.stats().record(started, snapshot.is_ok());
+                match snapshot {
+                    Ok(mut snapshot) => {
+                        for validator in snapshot.validators.iter_mut() {
+                            validator.probe = probes.get(validator);
+                        }
+                        registrations.annotate(&mut snapshot);
+                        log::debug!("Indexed block #{}", snapshot.block.number);
+                        cache.set(snapshot);
+                    }
+                    Err(error) => log::warn!("Failed to index block #{}: {}", block.number(), error),
+                }
+            }
+            Err(anyhow::anyhow!("Block subscription ended"))
+        }
+
+        /// Follow what happened in a block from its events
+        async fn observe_events(
+            registrations: &mut RegistrationTracker,
+            signing: Option<&SigningMonitor>,
+            cache: &SnapshotCache,
+            block: &Block<EntropyConfig, OnlineClient<EntropyConfig>>,
+        ) {
+            let time = match block_time(block).await {
+                Ok(time) => time,
+                Err(error) => {
+                    log::warn!("Failed to read the timestamp of block #{}: {}", block.number(), error);
+                    return;
+                }
+            };
+            if let Err(error) = registrations.observe(block, time).await {
+                log::warn!("Failed to read registration events of block #{}: {}", block.number(), error);
+            }
+            if let Some(signing) = signing {
+                if let Err(error) = signing.observe(block, time, cache).await {
+                    log::warn!("Failed to record signature events of block #{}: {}", block.number(), error);
+                }
+            }
+        }
+
+        /// Read all tables at the given block
+        pub async fn read_snapshot(
+            api: &OnlineClient<EntropyConfig>,
+            block_hash: H256,
+            block: BlockRef,
+        ) -> anyhow::Result<Snapshot> {
+            let (tables, pending_registrations) = futures::join!(
+                async {
+                    futures::try_join!(
+                        query_accounts(api, block_hash),
+                        query_programs(api, block_hash),
+                        query_validators(api, block_hash),
+                    )
+                },
+                query_pending_registrations(api, block_hash),
+            );
+            let (accounts, programs, validators) = tables?;
+            // Pending registrations are extra detail, so failing to read them should not stop
+            // everything else being shown
+            let pending_registrations = pending_registrations.unwrap_or_else(|error| {
+                log::warn!("Failed to read pending registrations at block #{}: {}", block.number, error);
+                Vec::new()
+            });
+            Ok(Snapshot {
+                block,
+                accounts,
+                programs,
+                validators,
+                pending_registrations,
+                recent_registrations: Vec::new(),
+            })
+        }
+    }
+}
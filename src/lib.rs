@@ -1,12 +1,34 @@
+pub mod account;
+pub mod alert;
+pub mod announcement;
+pub mod api;
 pub mod app;
+pub mod blocks;
+pub mod chain;
+pub mod chain_info;
+pub mod config;
+pub mod database;
+pub mod diff;
+pub mod download;
 pub mod error_template;
 pub mod fileserv;
+pub mod history;
+pub mod indexer;
+pub mod live;
+pub mod metrics;
+pub mod network;
+pub mod probe;
 pub mod program;
 pub mod registered_account;
+pub mod registration;
+pub mod signing;
+pub mod state;
+pub mod uptime;
 pub mod validator;
 
 use cfg_if::cfg_if;
 use leptos::*;
+use leptos_router::A;
 use pretty_bytes_rust::pretty_bytes;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -26,34 +48,97 @@ cfg_if! { if #[cfg(feature = "hydrate")] {
 }}
 
 #[server(GetChainEndpoint, "/api")]
-pub async fn get_chain_endpoint() -> Result<String, ServerFnError> {
-    Ok(std::env::var("ENTROPY_TESTNET_ENDPOINT").unwrap_or("ws://localhost:9944".to_string()))
+pub async fn get_chain_endpoint(network: String) -> Result<String, ServerFnError> {
+    Ok(chain_client(&network)?.endpoint().to_string())
 }
 
 cfg_if! { if #[cfg(feature = "ssr")] {
-    use entropy_testing_utils::{
-        test_client::{get_api, get_rpc},
-    };
     use entropy_testing_utils::chain_api::EntropyConfig;
     use subxt::{backend::legacy::LegacyRpcMethods, OnlineClient};
 
-    /// Backend function for getting the chain API
-    pub async fn get_api_rpc() -> Result<(
+    /// The named network, provided to server functions from the axum state
+    pub fn network_state(network: &str) -> Result<network::Network, ServerFnError> {
+        use_context::<network::Networks>()
+            .ok_or_else(|| ServerFnError::ServerError("Networks missing from context".to_string()))?
+            .get(network)
+            .cloned()
+            .ok_or_else(|| ServerFnError::Args(format!("Unknown network {}", network)))
+    }
+
+    /// The chain client of the named network
+    pub fn chain_client(network: &str) -> Result<chain::ChainClient, ServerFnError> {
+        Ok(network_state(network)?.chain)
+    }
+
+    /// The store of periodically recorded status of the named network
+    pub fn history_store(network: &str) -> Result<history::HistoryStore, ServerFnError> {
+        Ok(network_state(network)?.history)
+    }
+
+    /// The signature events recorded from the named network
+    pub fn signing_store(network: &str) -> Result<signing::SigningStore, ServerFnError> {
+        Ok(network_state(network)?.signing)
+    }
+
+    /// Announcements published by the network operators
+    pub fn announcement_store() -> Result<announcement::AnnouncementStore, ServerFnError> {
+        use_context::<announcement::AnnouncementStore>()
+            .ok_or_else(|| ServerFnError::ServerError("Announcement store missing from context".to_string()))
+    }
+
+    /// The cache the indexer keeps the latest snapshot of the named network in
+    pub fn snapshot_cache(network: &str) -> Result<indexer::SnapshotCache, ServerFnError> {
+        Ok(network_state(network)?.snapshots)
+    }
+
+    /// The latest threshold server probe results of the named network
+    pub fn probe_cache(network: &str) -> Result<probe::ProbeCache, ServerFnError> {
+        Ok(network_state(network)?.probes)
+    }
+
+    /// The most recently indexed snapshot of the named network's chain state
+    pub fn snapshot(network: &str) -> Result<std::sync::Arc<indexer::Snapshot>, ServerFnError> {
+        network_state(network)?.latest_snapshot().map_err(snapshot_error)
+    }
+
+    /// The given block number or hash of the named network, or the latest indexed block
+    pub async fn block_ref(network: &str, block: Option<String>) -> Result<indexer::BlockRef, ServerFnError> {
+        network_state(network)?.block_ref(block.as_deref()).await.map_err(snapshot_error)
+    }
+
+    /// A snapshot of the named network's chain state at the given block number or hash, or the
+    /// most recently indexed one
+    pub async fn snapshot_at(
+        network: &str,
+        block: Option<String>,
+    ) -> Result<std::sync::Arc<indexer::Snapshot>, ServerFnError> {
+        network_state(network)?.snapshot_at(block.as_deref()).await.map_err(snapshot_error)
+    }
+
+    /// A block which does not exist is a problem with the arguments, anything else with the server
+    fn snapshot_error(error: anyhow::Error) -> ServerFnError {
+        if error.is::<indexer::UnknownBlock>() {
+            ServerFnError::Args(error.to_string())
+        } else {
+            ServerFnError::ServerError(error.to_string())
+        }
+    }
+
+    /// Backend function for getting the chain API of the named network
+    pub async fn get_api_rpc(network: &str) -> Result<(
         OnlineClient<EntropyConfig>,
         LegacyRpcMethods<EntropyConfig>,
     ), ServerFnError> {
-
-        let endpoint_addr = get_chain_endpoint().await?;
-
-        // TODO a panic here means the endpoint is unreachable - deal with this gracefully
-        let api = get_api(&endpoint_addr).await?;
-        let rpc = get_rpc(&endpoint_addr).await?;
-        Ok((api, rpc))
+        chain_client(network)?
+            .get()
+            .await
+            .map_err(|e| ServerFnError::ServerError(e.to_string()))
     }
 }}
 
 /// For displaying Vec<u8> nicely
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
 pub struct HexVec(Vec<u8>);
 
 impl fmt::Display for HexVec {
@@ -79,6 +164,15 @@ impl fmt::Debug for HexVec {
     }
 }
 
+/// For displaying full length hex hashes in the same shortened form as `HexVec`
+pub fn display_hash(hash: &str) -> String {
+    if hash.len() > 10 {
+        format!("{}…{}", &hash[..6], &hash[hash.len() - 4..])
+    } else {
+        hash.to_string()
+    }
+}
+
 /// For diplaying sizes nicely
 pub fn display_bytes(bytes: u64) -> String {
     match bytes {
@@ -94,6 +188,23 @@ pub fn display_bytes(bytes: u64) -> String {
     }
 }
 
+/// For displaying unix timestamps (in seconds) nicely
+pub fn display_timestamp(seconds: u64) -> String {
+    match chrono::DateTime::from_timestamp(seconds as i64, 0) {
+        Some(time) => time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        None => seconds.to_string(),
+    }
+}
+
+/// For displaying lengths of time (in seconds) briefly
+pub fn display_duration(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m", seconds / 60),
+        _ => format!("{}h {}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
 /// A table with given headings and a title
 #[component]
 pub fn DetailsTable(
@@ -130,6 +241,50 @@ pub fn DetailsTable(
     }
 }
 
+/// A two column table of labelled values with a title
+#[component]
+pub fn DetailsList(title: String, children: Children) -> impl IntoView {
+    view! {
+        <h2 class="my-4 block font-sans text-xl antialiased leading-snug tracking-normal mt-4 text-gray-700 break-all">
+            {title}
+        </h2>
+        <div class="relative flex flex-col w-full h-full text-gray-700 bg-white shadow-md rounded-xl bg-clip-border">
+            <table class="w-full text-left table-auto">
+                <tbody>{children()}</tbody>
+            </table>
+        </div>
+    }
+}
+
+/// A row of a `DetailsList`. Children should be table cells.
+#[component]
+pub fn DetailRow(label: &'static str, children: Children) -> impl IntoView {
+    view! {
+        <tr class="hover:bg-gray-200">
+            <th class="p-4 border-r border-blue-gray-100 bg-blue-50 w-1/4">
+                <p class="block font-sans text-sm antialiased font-normal leading-none text-blue-gray-900 opacity-70">
+                    {label}
+                </p>
+            </th>
+            {children()}
+        </tr>
+    }
+}
+
+/// Table data linking to another page
+#[component]
+pub fn LinkedValue(value: String, href: String) -> impl IntoView {
+    view! {
+        <td>
+            <p class="block font-sans text-sm antialiased font-normal leading-normal text-blue-gray-900">
+                <A href=href class="hover:font-extrabold p-4 underline">
+                    <code>{value}</code>
+                </A>
+            </p>
+        </td>
+    }
+}
+
 /// Copyable table data
 #[component]
 pub fn DisplayValue(value: String, long_value: Option<String>) -> impl IntoView {
@@ -167,3 +322,15 @@ pub fn DisplayValue(value: String, long_value: Option<String>) -> impl IntoView
         </td>
     }
 }
+
+/// Table row shown in place of data which could not be fetched
+#[component]
+pub fn UnavailableRow(error: ServerFnError) -> impl IntoView {
+    view! {
+        <tr>
+            <td class="p-4 text-sm text-gray-500" title=error.to_string()>
+                "Unavailable"
+            </td>
+        </tr>
+    }
+}
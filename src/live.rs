@@ -0,0 +1,212 @@
+use leptos::*;
+
+/// Route on which new snapshots are pushed to the browser as server-sent events
+pub const SNAPSHOT_EVENTS_PATH: &str = "/events/snapshots";
+
+/// Name of the server-sent event carrying a JSON encoded snapshot
+pub const SNAPSHOT_EVENT: &str = "snapshot";
+
+/// Where the snapshots of the given network are pushed
+pub fn snapshot_events_url(network: &str) -> String {
+    format!("{}?network={}", SNAPSHOT_EVENTS_PATH, network)
+}
+
+/// Shows whether the page is receiving live updates
+#[component]
+pub fn LiveIndicator(live: ReadSignal<bool>) -> impl IntoView {
+    view! {
+        <span class="inline-flex items-center gap-2 text-sm text-blue-gray-900">
+            <span class=move || {
+                if live.get() {
+                    "inline-block w-2 h-2 rounded-full bg-green-500 animate-pulse"
+                } else {
+                    "inline-block w-2 h-2 rounded-full bg-gray-400"
+                }
+            }></span>
+            {move || if live.get() { "Live" } else { "Not live - reconnecting" }}
+        </span>
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "hydrate")] {
+        use crate::indexer::Snapshot;
+        use std::{cell::{Cell, RefCell}, rc::Rc, time::Duration};
+        use wasm_bindgen::{closure::Closure, JsCast};
+        use web_sys::{EventSource, MessageEvent};
+
+        /// How long to wait before re-opening a closed event stream
+        const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+        /// Handlers attached to the open event stream, which must live as long as the stream does
+        struct Callbacks {
+            _on_open: Closure<dyn FnMut()>,
+            _on_snapshot: Closure<dyn FnMut(MessageEvent)>,
+            _on_error: Closure<dyn FnMut()>,
+        }
+
+        #[derive(Default)]
+        struct Shared {
+            source: RefCell<Option<EventSource>>,
+            callbacks: RefCell<Option<Callbacks>>,
+            /// The network whose snapshots are streamed
+            network: RefCell<String>,
+            stopped: Cell<bool>,
+            /// Set while an earlier block is being viewed
+            paused: Cell<bool>,
+        }
+
+        #[derive(Clone)]
+        struct Subscription {
+            shared: Rc<Shared>,
+            snapshot: Resource<(String, Option<String>), Result<Snapshot, ServerFnError>>,
+            set_live: WriteSignal<bool>,
+        }
+
+        impl Subscription {
+            fn close(&self) {
+                if let Some(source) = self.shared.source.borrow_mut().take() {
+                    source.close();
+                }
+                self.shared.callbacks.borrow_mut().take();
+            }
+        }
+
+        /// Keep the given resource up to date with snapshots of the given network pushed from the
+        /// server, reconnecting whenever the stream closes or the network changes. Nothing is
+        /// streamed while an earlier block is being viewed. The stream is closed when the calling
+        /// component is cleaned up.
+        pub fn subscribe_to_snapshots(
+            network: Memo<String>,
+            block: Memo<Option<String>>,
+            snapshot: Resource<(String, Option<String>), Result<Snapshot, ServerFnError>>,
+            set_live: WriteSignal<bool>,
+        ) {
+            let subscription = Subscription {
+                shared: Rc::new(Shared::default()),
+                snapshot,
+                set_live,
+            };
+            create_effect({
+                let subscription = subscription.clone();
+                move |_| {
+                    *subscription.shared.network.borrow_mut() = network.get();
+                    let paused = block.with(Option::is_some);
+                    subscription.shared.paused.set(paused);
+                    if paused {
+                        subscription.close();
+                        subscription.set_live.set(false);
+                    } else {
+                        open_event_source(subscription.clone());
+                    }
+                }
+            });
+            on_cleanup(move || {
+                subscription.shared.stopped.set(true);
+                subscription.close();
+            });
+        }
+
+        fn open_event_source(subscription: Subscription) {
+            if subscription.shared.stopped.get() || subscription.shared.paused.get() {
+                return;
+            }
+            // Only one stream is open at a time, even if a reconnect races a change of network
+            subscription.close();
+            let url = snapshot_events_url(&subscription.shared.network.borrow());
+            let source = match EventSource::new(&url) {
+                Ok(source) => source,
+                Err(error) => {
+                    log::warn!("Failed to open snapshot event stream: {:?}", error);
+                    return;
+                }
+            };
+
+            let set_live = subscription.set_live;
+            let on_open = Closure::<dyn FnMut()>::new(move || set_live.set(true));
+            source.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+
+            let snapshot = subscription.snapshot;
+            let on_snapshot = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                match event.data().as_string().map(|data| serde_json::from_str::<Snapshot>(&data)) {
+                    Some(Ok(new_snapshot)) => snapshot.set(Ok(new_snapshot)),
+                    _ => log::warn!("Could not decode snapshot event"),
+                }
+            });
+            let _ = source.add_event_listener_with_callback(SNAPSHOT_EVENT, on_snapshot.as_ref().unchecked_ref());
+
+            // The browser retries by itself unless the stream is closed, in which case we start over.
+            // The handler is owned by the subscription, so it only holds on to it weakly.
+            let on_error = {
+                let shared = Rc::downgrade(&subscription.shared);
+                Closure::<dyn FnMut()>::new(move || {
+                    set_live.set(false);
+                    let Some(shared) = shared.upgrade() else {
+                        return;
+                    };
+                    let closed = shared
+                        .source
+                        .borrow()
+                        .as_ref()
+                        .map(|source| source.ready_state() == EventSource::CLOSED)
+                        .unwrap_or(false);
+                    if closed {
+                        let shared = Rc::downgrade(&shared);
+                        set_timeout(
+                            move || {
+                                if let Some(shared) = shared.upgrade() {
+                                    open_event_source(Subscription { shared, snapshot, set_live });
+                                }
+                            },
+                            RECONNECT_DELAY,
+                        );
+                    }
+                })
+            };
+            source.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+            *subscription.shared.source.borrow_mut() = Some(source);
+            *subscription.shared.callbacks.borrow_mut() = Some(Callbacks {
+                _on_open: on_open,
+                _on_snapshot: on_snapshot,
+                _on_error: on_error,
+            });
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::{network::NetworkQuery, state::AppState};
+        use axum::{
+            extract::{Query, State},
+            http::StatusCode,
+            response::sse::{Event, KeepAlive, Sse},
+        };
+        use futures::{stream, Stream, StreamExt};
+        use tokio::sync::broadcast::error::RecvError;
+
+        /// Stream the current snapshot of a network followed by each new one as server-sent events
+        pub async fn snapshot_events(
+            State(state): State<AppState>,
+            Query(query): Query<NetworkQuery>,
+        ) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, (StatusCode, String)> {
+            let cache = state.networks.resolve(query.network.as_deref())?.snapshots.clone();
+            let current = stream::iter(cache.get());
+            let updates = stream::unfold(cache.subscribe(), |mut receiver| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(snapshot) => return Some((snapshot, receiver)),
+                        // A slow client only needs the latest snapshot, so skipping some is fine
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            });
+            let events = current
+                .chain(updates)
+                .map(|snapshot| Event::default().event(SNAPSHOT_EVENT).json_data(snapshot.as_ref()));
+            Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+        }
+    }
+}
@@ -1,58 +1,206 @@
 #[cfg(feature = "ssr")]
 #[tokio::main]
-async fn main() {
-    use axum::{routing::post, Router};
+async fn main() -> anyhow::Result<()> {
+    use anyhow::Context;
+    use axum::{
+        response::Redirect,
+        routing::{get, post},
+        Router,
+    };
     use axum_server::tls_rustls::RustlsConfig;
-    use entropy_network_status_page::{app::*, fileserv::file_and_error_handler};
+    use entropy_network_status_page::{
+        alert::{run_alerts, AlertConfig},
+        announcement::{admin_routes, AnnouncementStore},
+        api::{self, api_docs, openapi, API_DOCS_ROUTE, OPENAPI_ROUTE},
+        app::*,
+        chain::ChainClient,
+        config::Config,
+        database::Database,
+        download::{
+            program_bytecode, program_configuration_interface, BYTECODE_ROUTE,
+            CONFIGURATION_INTERFACE_ROUTE,
+        },
+        fileserv::file_and_error_handler,
+        history::{run_recorder, HistoryStore},
+        indexer::{run_indexer, HistoricSnapshots, SnapshotCache},
+        live::{snapshot_events, SNAPSHOT_EVENTS_PATH},
+        metrics::{metrics, METRICS_ROUTE},
+        network::{Network, Networks},
+        probe::{run_prober, ProbeCache},
+        signing::{SigningMonitor, SigningStore},
+        state::AppState,
+    };
     use leptos::*;
     use leptos_axum::{generate_route_list, LeptosRoutes};
-    use std::path::PathBuf;
+    use std::time::Duration;
 
     simple_logger::init_with_level(log::Level::Debug).expect("couldn't initialize logging");
 
+    let config = Config::load()?;
+
     // Setting get_configuration(None) means we'll be using cargo-leptos's env values
     // For deployment these variables are:
     // <https://github.com/leptos-rs/start-axum#executing-a-server-on-a-remote-machine-without-the-toolchain>
     // Alternately a file can be specified such as Some("Cargo.toml")
     // The file would need to be included with the executable when moved to deployment
-    let conf = get_configuration(None).await.unwrap();
-    let leptos_options = conf.leptos_options;
+    let conf = get_configuration(None)
+        .await
+        .context("Invalid cargo-leptos configuration")?;
+    let mut leptos_options = conf.leptos_options;
+    if let Some(site_addr) = config.site_addr {
+        leptos_options.site_addr = site_addr;
+    }
     let addr = leptos_options.site_addr;
     let routes = generate_route_list(App);
 
+    let database = Database::open(&config.database_path)?;
+    let historic = HistoricSnapshots::default();
+    let mut networks = Vec::new();
+    for network_config in &config.networks {
+        // A single chain client per network shared by all requests, kept alive by a background
+        // health check
+        let chain = ChainClient::new(
+            network_config.endpoint.clone(),
+            config.connect_timeout(),
+            config.health_check_interval(),
+        );
+        tokio::spawn(chain.clone().watch());
+
+        // Record signature requests for the signing activity feed, as the indexer follows blocks
+        let signing = SigningStore::open(database.clone(), &network_config.name, config.history_retention())
+            .with_context(|| format!("Cannot open signing activity of network {}", network_config.name))?;
+        let signing_monitor = config
+            .features
+            .signing
+            .then(|| SigningMonitor::new(signing.clone(), config.signing.events.clone()));
+
+        // Keep a snapshot of chain state up to date with each finalized block
+        let snapshots = SnapshotCache::default();
+        let probes = ProbeCache::default();
+        tokio::spawn(run_indexer(chain.clone(), snapshots.clone(), probes.clone(), signing_monitor));
+
+        // Check that each validator's threshold server is responding
+        if config.features.probes {
+            tokio::spawn(run_prober(
+                snapshots.clone(),
+                probes.clone(),
+                config.probe_interval(),
+                config.probe_timeout(),
+            ));
+        }
+
+        // Keep a record of network status for the history charts
+        let history = HistoryStore::open(database.clone(), &network_config.name, config.history_retention())
+            .with_context(|| format!("Cannot open history of network {}", network_config.name))?;
+        if config.features.history {
+            tokio::spawn(run_recorder(
+                history.clone(),
+                chain.clone(),
+                snapshots.clone(),
+                probes.clone(),
+                config.record_interval(),
+            ));
+        }
+
+        networks.push(Network {
+            name: network_config.name.clone(),
+            label: network_config.label(),
+            chain,
+            snapshots,
+            historic: historic.clone(),
+            probes,
+            history,
+            signing,
+        });
+    }
+    let networks = Networks::new(networks);
+
+    // Announcements published through the admin API
+    let announcements = AnnouncementStore::open(database)
+        .await
+        .context("Cannot load announcements")?;
+
+    // Notify the configured webhooks when something goes wrong
+    if !config.alerts.webhooks.is_empty() {
+        let alert_config = AlertConfig {
+            webhooks: config.alerts.webhooks.clone(),
+            finality_lag_blocks: config.alerts.finality_lag_blocks,
+            tss_down_after: Duration::from_secs(config.alerts.tss_down_minutes * 60),
+            evaluation_interval: Duration::from_secs(config.alerts.evaluation_interval_secs),
+        };
+        for network in networks.iter() {
+            tokio::spawn(run_alerts(alert_config.clone(), network.clone(), announcements.clone()));
+        }
+    }
+
+    // Pages live under the network they show, so the site root goes to the default network
+    let default_network_path = format!("/{}", networks.default_network().name);
+
+    let state = AppState {
+        leptos_options,
+        networks,
+        announcements,
+        admin_token: config.admin_token.clone(),
+    };
+
     // build our application with a route
-    let app = Router::new()
-        .route("/api/*fn_name", post(leptos_axum::handle_server_fns))
-        .leptos_routes(&leptos_options, routes, App)
+    let mut app = Router::new()
+        .route("/", get(move || std::future::ready(Redirect::temporary(&default_network_path))))
+        // Would otherwise be taken for the name of a network
+        .route("/favicon.ico", get(file_and_error_handler))
+        .route("/api/*fn_name", post(server_fn_handler))
+        .route(SNAPSHOT_EVENTS_PATH, get(snapshot_events))
+        .route(BYTECODE_ROUTE, get(program_bytecode))
+        .route(CONFIGURATION_INTERFACE_ROUTE, get(program_configuration_interface))
+        .nest("/admin", admin_routes());
+    if config.features.api {
+        app = app
+            .nest("/v1", api::routes())
+            .route(OPENAPI_ROUTE, get(openapi))
+            .route(API_DOCS_ROUTE, get(api_docs));
+    }
+    if config.features.metrics {
+        app = app.route(METRICS_ROUTE, get(metrics));
+    }
+    let app = app
+        .leptos_routes_with_context(
+            &state,
+            routes,
+            {
+                let state = state.clone();
+                move || state.provide()
+            },
+            App,
+        )
         .fallback(file_and_error_handler)
-        .with_state(leptos_options);
+        .with_state(state);
 
-    match std::env::var("TLS_CERT_LOCATION") {
-        Ok(location) => {
+    match &config.tls {
+        Some(tls) => {
             // optional: spawn a second server to redirect http requests to this server
-            tokio::spawn(redirect_http_to_https(addr.ip(), 80, 443));
+            if let Some(http_port) = tls.http_redirect_port {
+                tokio::spawn(redirect_http_to_https(addr.ip(), http_port, addr.port()));
+            }
 
-            let config = RustlsConfig::from_pem_file(
-                PathBuf::from(&location).join("fullchain.pem"),
-                PathBuf::from(&location).join("privkey.pem"),
-            )
-            .await
-            .unwrap();
+            let rustls_config = RustlsConfig::from_pem_file(tls.cert_path(), tls.key_path())
+                .await
+                .context("Cannot load TLS certificate")?;
 
             log::info!("listening on https://{}", &addr);
-            axum_server::bind_rustls(addr, config)
+            axum_server::bind_rustls(addr, rustls_config)
                 .serve(app.into_make_service())
                 .await
-                .unwrap();
+                .with_context(|| format!("Cannot serve on {}", addr))?;
         }
-        Err(_) => {
+        None => {
             log::info!("listening on http://{}", &addr);
-            axum::Server::bind(&addr)
+            axum::Server::try_bind(&addr)
+                .with_context(|| format!("Cannot listen on {}", addr))?
                 .serve(app.into_make_service())
-                .await
-                .unwrap();
+                .await?;
         }
     }
+    Ok(())
 }
 
 #[cfg(not(feature = "ssr"))]
@@ -62,6 +210,25 @@ pub fn main() {
     // see lib.rs for hydration function instead
 }
 
+/// Handle server function calls, giving them access to the shared state
+#[cfg(feature = "ssr")]
+async fn server_fn_handler(
+    axum::extract::State(state): axum::extract::State<entropy_network_status_page::state::AppState>,
+    path: axum::extract::Path<String>,
+    headers: axum::http::HeaderMap,
+    raw_query: axum::extract::RawQuery,
+    request: axum::http::Request<axum::body::Body>,
+) -> impl axum::response::IntoResponse {
+    leptos_axum::handle_server_fns_with_context(
+        path,
+        headers,
+        raw_query,
+        move || state.provide(),
+        request,
+    )
+    .await
+}
+
 #[cfg(feature = "ssr")]
 async fn redirect_http_to_https(ip_addr: std::net::IpAddr, http_port: u16, https_port: u16) {
     use axum::{
@@ -103,11 +270,14 @@ async fn redirect_http_to_https(ip_addr: std::net::IpAddr, http_port: u16, https
     let addr = std::net::SocketAddr::from((ip_addr, http_port));
     log::debug!("listening on {}", &addr);
 
-    axum::Server::bind(&addr)
-        .serve(redirect.into_make_service())
-        .await
-        .unwrap();
-    // axum::serve(listener, redirect.into_make_service())
-    //     .await
-    //     .unwrap();
+    let server = match axum::Server::try_bind(&addr) {
+        Ok(server) => server,
+        Err(error) => {
+            log::error!("Cannot listen on {} to redirect to HTTPS: {}", addr, error);
+            return;
+        }
+    };
+    if let Err(error) = server.serve(redirect.into_make_service()).await {
+        log::error!("HTTPS redirect server failed: {}", error);
+    }
 }
@@ -0,0 +1,197 @@
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    use crate::state::AppState;
+    use axum::{
+        extract::State,
+        http::{header, HeaderValue},
+        response::{IntoResponse, Response},
+    };
+    use std::fmt::Write;
+
+    /// Route serving metrics in the Prometheus text format
+    pub const METRICS_ROUTE: &str = "/metrics";
+
+    /// Key visibilities accounts are counted by, so that each is reported even when zero
+    const KEY_VISIBILITIES: [&str; 3] = ["Public", "Permissioned", "Private"];
+
+    /// Export the latest snapshot, probe results and chain connection of each network as
+    /// Prometheus metrics
+    pub async fn metrics(State(state): State<AppState>) -> Response {
+        let mut metrics = Metrics::default();
+
+        for network in state.networks.iter() {
+            let labels = [("network", network.name.as_str())];
+            metrics.gauge(
+                "entropy_chain_up",
+                "Whether the chain endpoint can be reached",
+                &[("network", network.name.as_str()), ("endpoint", network.chain.endpoint())],
+                network.chain.status().is_reachable() as u64,
+            );
+            // Heights from the latest health check, so that scraping makes no requests to the chain
+            if let Some((best, finalized)) = network.chain.last_block_heights() {
+                metrics.gauge("entropy_best_block", "Number of the best block", &labels, best);
+                metrics.gauge("entropy_finalized_block", "Number of the latest finalized block", &labels, finalized);
+            }
+
+            let stats = network.chain.stats();
+            metrics.counter(
+                "entropy_chain_rpc_requests_total",
+                "Requests made to the chain endpoint",
+                &labels,
+                stats.requests(),
+            );
+            metrics.counter(
+                "entropy_chain_rpc_errors_total",
+                "Requests to the chain endpoint which failed",
+                &labels,
+                stats.errors(),
+            );
+            metrics.counter(
+                "entropy_chain_rpc_latency_seconds_total",
+                "Time spent waiting on requests to the chain endpoint",
+                &labels,
+                stats.latency_ms_total() as f64 / 1000.0,
+            );
+
+            let Some(snapshot) = network.snapshots.get() else {
+                continue;
+            };
+            metrics.gauge(
+                "entropy_indexed_block",
+                "Number of the block the reported state was read at",
+                &labels,
+                snapshot.block.number,
+            );
+            for visibility in KEY_VISIBILITIES {
+                let count = snapshot
+                    .accounts
+                    .iter()
+                    .filter(|account| account.key_visibility.0 == visibility)
+                    .count();
+                metrics.gauge(
+                    "entropy_registered_accounts",
+                    "Number of registered accounts",
+                    &[("network", network.name.as_str()), ("key_visibility", visibility.to_lowercase().as_str())],
+                    count,
+                );
+            }
+            metrics.gauge(
+                "entropy_pending_registrations",
+                "Number of registrations waiting for confirmation",
+                &labels,
+                snapshot.pending_registrations.len(),
+            );
+            metrics.gauge(
+                "entropy_programs",
+                "Number of stored programs",
+                &labels,
+                snapshot.programs.len(),
+            );
+            metrics.gauge(
+                "entropy_program_bytecode_bytes",
+                "Total size of stored program bytecode",
+                &labels,
+                snapshot.programs.iter().map(|program| program.size).sum::<usize>(),
+            );
+            metrics.gauge(
+                "entropy_validators",
+                "Number of validators with a threshold server",
+                &labels,
+                snapshot.validators.len(),
+            );
+            for validator in &snapshot.validators {
+                let Some(probe) = network.probes.get(validator) else {
+                    continue;
+                };
+                let stash_account = validator.stash_account.to_string();
+                let labels = [
+                    ("network", network.name.as_str()),
+                    ("stash_account", stash_account.as_str()),
+                    ("endpoint", validator.endpoint.as_str()),
+                ];
+                metrics.gauge(
+                    "entropy_tss_up",
+                    "Whether the threshold server responded to the latest check",
+                    &labels,
+                    probe.reachable as u64,
+                );
+                if let Some(latency_ms) = probe.latency_ms {
+                    metrics.gauge(
+                        "entropy_tss_latency_seconds",
+                        "Response time of the threshold server in the latest check",
+                        &labels,
+                        latency_ms as f64 / 1000.0,
+                    );
+                }
+            }
+        }
+
+        (
+            [(header::CONTENT_TYPE, HeaderValue::from_static("text/plain; version=0.0.4"))],
+            metrics.render(),
+        )
+            .into_response()
+    }
+
+    /// Metrics in the Prometheus text exposition format, grouped by name as the format requires
+    #[derive(Default)]
+    struct Metrics {
+        families: Vec<Family>,
+    }
+
+    /// A metric and each of its labelled series
+    struct Family {
+        name: String,
+        help: String,
+        kind: &'static str,
+        samples: Vec<String>,
+    }
+
+    impl Metrics {
+        fn gauge(&mut self, name: &str, help: &str, labels: &[(&str, &str)], value: impl ToString) {
+            self.write(name, help, "gauge", labels, value.to_string());
+        }
+
+        fn counter(&mut self, name: &str, help: &str, labels: &[(&str, &str)], value: impl ToString) {
+            self.write(name, help, "counter", labels, value.to_string());
+        }
+
+        fn write(&mut self, name: &str, help: &str, kind: &'static str, labels: &[(&str, &str)], value: String) {
+            let labels = labels
+                .iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+                .collect::<Vec<_>>();
+            let sample = if labels.is_empty() {
+                format!("{} {}", name, value)
+            } else {
+                format!("{}{{{}}} {}", name, labels.join(","), value)
+            };
+            match self.families.iter_mut().find(|family| family.name == name) {
+                Some(family) => family.samples.push(sample),
+                None => self.families.push(Family {
+                    name: name.to_string(),
+                    help: help.to_string(),
+                    kind,
+                    samples: vec![sample],
+                }),
+            }
+        }
+
+        fn render(&self) -> String {
+            let mut text = String::new();
+            for family in &self.families {
+                let _ = writeln!(text, "# HELP {} {}", family.name, family.help);
+                let _ = writeln!(text, "# TYPE {} {}", family.name, family.kind);
+                for sample in &family.samples {
+                    let _ = writeln!(text, "{}", sample);
+                }
+            }
+            text
+        }
+    }
+
+    fn escape_label(value: &str) -> String {
+        value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+    }
+}}
@@ -0,0 +1,251 @@
+use crate::error_template::{AppError, ErrorTemplate};
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+/// A monitored network, as listed in the network switcher
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub struct NetworkSummary {
+    /// Used in URLs
+    pub name: String,
+    pub label: String,
+    pub endpoint: String,
+}
+
+#[server(GetNetworks, "/api")]
+pub async fn get_networks() -> Result<Vec<NetworkSummary>, ServerFnError> {
+    let networks = use_context::<Networks>()
+        .ok_or_else(|| ServerFnError::ServerError("Networks missing from context".to_string()))?;
+    Ok(networks.summaries())
+}
+
+/// The name of the network whose pages are being shown, from the route
+pub fn use_network() -> Memo<String> {
+    let params = use_params_map();
+    create_memo(move |_| params.with(|params| params.get("network").cloned().unwrap_or_default()))
+}
+
+/// Link to a page of the network currently shown, given its path within the network
+pub fn network_href(path: &str) -> String {
+    let network = use_params_map().with_untracked(|params| params.get("network").cloned().unwrap_or_default());
+    format!("/{}{}", network, path)
+}
+
+/// The same page on another network, so `/testnet/history` becomes `/staging/history`
+fn switch_network(path: &str, network: &str) -> String {
+    match path.trim_start_matches('/').split_once('/') {
+        Some((_, rest)) if !rest.is_empty() => format!("/{}/{}", network, rest),
+        _ => format!("/{}", network),
+    }
+}
+
+/// The pages of one network, with a switcher for choosing another. Networks which are not
+/// configured are not found.
+#[component]
+pub fn NetworkLayout() -> impl IntoView {
+    let network = use_network();
+    let networks = create_resource(|| (), |_| get_networks());
+    // The server knows straight away, so the status is set before any of the page is sent
+    #[cfg(feature = "ssr")]
+    if use_context::<Networks>().map_or(false, |networks| networks.get(&network.get_untracked()).is_none()) {
+        if let Some(response) = use_context::<leptos_axum::ResponseOptions>() {
+            response.set_status(AppError::NotFound.status_code());
+        }
+    }
+    // If the networks cannot be listed, the pages explain what went wrong
+    let known = create_memo(move |_| {
+        networks.get().map(|networks| {
+            networks.map_or(true, |networks| networks.iter().any(|summary| summary.name == network.get()))
+        })
+    });
+    view! {
+        <Transition fallback=|| ()>
+            {move || match known.get() {
+                Some(true) => {
+                    view! {
+                        <div class="container mx-auto flex justify-end pt-4">
+                            <NetworkSwitcher/>
+                        </div>
+                        <Outlet/>
+                    }
+                        .into_view()
+                }
+                Some(false) => {
+                    let mut outside_errors = Errors::default();
+                    outside_errors.insert_with_default_key(AppError::NotFound);
+                    view! { <ErrorTemplate outside_errors/> }.into_view()
+                }
+                None => ().into_view(),
+            }}
+
+        </Transition>
+    }
+}
+
+/// A dropdown of the configured networks. Renders nothing when there is only one.
+#[component]
+pub fn NetworkSwitcher() -> impl IntoView {
+    let network = use_network();
+    let networks = create_resource(|| (), |_| get_networks());
+    let location = use_location();
+    let navigate = use_navigate();
+    let switch = move |event| {
+        let path = switch_network(&location.pathname.get_untracked(), &event_target_value(&event));
+        navigate(&path, Default::default());
+    };
+    view! {
+        <Transition fallback=|| ()>
+            {move || {
+                networks
+                    .get()
+                    .and_then(|networks| networks.ok())
+                    .filter(|networks| networks.len() > 1)
+                    .map(|networks| {
+                        view! {
+                            <label class="text-sm text-blue-gray-900">
+                                "Network "
+                                <select class="ml-2 px-2 py-1 rounded-md bg-blue-50" on:change=switch.clone()>
+                                    {networks
+                                        .into_iter()
+                                        .map(|summary| {
+                                            let name = summary.name.clone();
+                                            view! {
+                                                <option
+                                                    value=summary.name
+                                                    title=summary.endpoint
+                                                    selected=move || network.get() == name
+                                                >
+                                                    {summary.label}
+                                                </option>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </select>
+                            </label>
+                        }
+                    })
+            }}
+
+        </Transition>
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::{
+            chain::ChainClient,
+            history::HistoryStore,
+            indexer::{find_block, read_snapshot, BlockRef, HistoricSnapshots, Snapshot, SnapshotCache},
+            probe::ProbeCache,
+            signing::SigningStore,
+        };
+        use anyhow::anyhow;
+        use axum::http::StatusCode;
+        use std::sync::Arc;
+        use utoipa::IntoParams;
+
+        /// Query parameter choosing the network for routes outside a network's pages
+        #[derive(Deserialize, IntoParams)]
+        #[into_params(parameter_in = Query)]
+        pub struct NetworkQuery {
+            /// Name of the network, defaulting to the first configured
+            pub network: Option<String>,
+        }
+
+        /// The chain client and everything read from one network
+        #[derive(Clone)]
+        pub struct Network {
+            pub name: String,
+            pub label: String,
+            pub chain: ChainClient,
+            pub snapshots: SnapshotCache,
+            /// Snapshots of earlier blocks, shared with the other networks
+            pub historic: HistoricSnapshots,
+            pub probes: ProbeCache,
+            pub history: HistoryStore,
+            pub signing: SigningStore,
+        }
+
+        impl Network {
+            /// The most recently indexed snapshot of chain state
+            pub fn latest_snapshot(&self) -> anyhow::Result<Arc<Snapshot>> {
+                self.snapshots.get().ok_or_else(|| anyhow!("No finalized block has been indexed yet"))
+            }
+
+            /// The given block number or hash, or the latest indexed block. A block which does
+            /// not exist is an `UnknownBlock` error.
+            pub async fn block_ref(&self, block: Option<&str>) -> anyhow::Result<BlockRef> {
+                match block {
+                    None => Ok(self.latest_snapshot()?.block.clone()),
+                    Some(block) => {
+                        let (_api, rpc) = self.chain.get().await?;
+                        find_block(&rpc, block).await
+                    }
+                }
+            }
+
+            /// Chain state at the given block number or hash, or the most recently indexed
+            /// snapshot. Earlier blocks are read on demand, so need an archive node unless they
+            /// are recent.
+            pub async fn snapshot_at(&self, block: Option<&str>) -> anyhow::Result<Arc<Snapshot>> {
+                if block.is_none() {
+                    return self.latest_snapshot();
+                }
+                let block = self.block_ref(block).await?;
+                if let Some(snapshot) = self.historic.get(&self.name, &block.hash) {
+                    return Ok(snapshot);
+                }
+                let block_hash = block.block_hash()?;
+                let (api, _rpc) = self.chain.get().await?;
+                let snapshot = Arc::new(read_snapshot(&api, block_hash, block).await?);
+                self.historic.insert(&self.name, snapshot.clone());
+                Ok(snapshot)
+            }
+        }
+
+        /// All monitored networks, in the configured order. There is always at least one.
+        #[derive(Clone)]
+        pub struct Networks(Arc<Vec<Network>>);
+
+        impl Networks {
+            pub fn new(networks: Vec<Network>) -> Self {
+                assert!(!networks.is_empty(), "At least one network must be configured");
+                Networks(Arc::new(networks))
+            }
+
+            pub fn get(&self, name: &str) -> Option<&Network> {
+                self.0.iter().find(|network| network.name == name)
+            }
+
+            /// The network shown when none is chosen
+            pub fn default_network(&self) -> &Network {
+                &self.0[0]
+            }
+
+            /// The named network, or the default one if none is named
+            pub fn resolve(&self, name: Option<&str>) -> Result<&Network, (StatusCode, String)> {
+                match name {
+                    None => Ok(self.default_network()),
+                    Some(name) => self
+                        .get(name)
+                        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Unknown network {}", name))),
+                }
+            }
+
+            pub fn iter(&self) -> impl Iterator<Item = &Network> {
+                self.0.iter()
+            }
+
+            pub fn summaries(&self) -> Vec<NetworkSummary> {
+                self.iter()
+                    .map(|network| NetworkSummary {
+                        name: network.name.clone(),
+                        label: network.label.clone(),
+                        endpoint: network.chain.endpoint().to_string(),
+                    })
+                    .collect()
+            }
+        }
+    }
+}
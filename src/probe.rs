@@ -0,0 +1,243 @@
+use crate::{display_timestamp, HexVec};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+/// Whether a threshold server holds the x25519 key registered for it on chain
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub enum KeyCheck {
+    Match,
+    /// The server advertises a different key, so encrypted signing requests to it will fail
+    Mismatch { advertised: HexVec },
+    /// The advertised key could not be fetched
+    Unknown(String),
+}
+
+/// The result of checking whether a validator's threshold server responds
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub struct ProbeResult {
+    pub reachable: bool,
+    pub latency_ms: Option<u64>,
+    /// The version the server reports, if it reports one
+    pub version: Option<String>,
+    pub error: Option<String>,
+    /// Unix time in seconds of this check
+    pub checked_at: u64,
+    /// Unix time in seconds the server last responded
+    pub last_seen: Option<u64>,
+    pub key_check: KeyCheck,
+}
+
+/// Table cells showing a probe result as a status pill, latency and last seen time
+#[component]
+pub fn ProbeStatus(probe: Option<ProbeResult>) -> impl IntoView {
+    let (label, color, title) = match &probe {
+        None => ("Unknown", "gray", "Not yet checked".to_string()),
+        Some(probe) if probe.reachable => (
+            "Up",
+            "green",
+            probe.version.clone().unwrap_or("Version unknown".to_string()),
+        ),
+        Some(probe) => ("Down", "red", probe.error.clone().unwrap_or_default()),
+    };
+    let style = format!("relative grid items-center px-2 py-1 font-sans text-xs font-bold text-{}-900 uppercase rounded-md select-none whitespace-nowrap bg-{}-500/20", color, color);
+    let latency = probe
+        .as_ref()
+        .and_then(|probe| probe.latency_ms)
+        .map(|latency| format!("{} ms", latency))
+        .unwrap_or_default();
+    let last_seen = probe
+        .and_then(|probe| probe.last_seen)
+        .map(display_timestamp)
+        .unwrap_or("Never".to_string());
+    view! {
+        <td class="px-4" title=title>
+            <div class=style>{label}</div>
+        </td>
+        <td class="p-4">
+            <p class="block font-sans text-sm antialiased font-normal leading-normal text-blue-gray-900">
+                {latency}
+            </p>
+        </td>
+        <td class="p-4">
+            <p class="block font-sans text-sm antialiased font-normal leading-normal text-blue-gray-900">
+                {last_seen}
+            </p>
+        </td>
+    }
+}
+
+/// Table cell showing the result of comparing on-chain and advertised x25519 keys
+#[component]
+pub fn KeyCheckStatus(key_check: Option<KeyCheck>) -> impl IntoView {
+    let (label, color, title) = match key_check {
+        None => ("Unknown", "gray", "Not yet checked".to_string()),
+        Some(KeyCheck::Match) => ("Match", "green", "Server holds the registered key".to_string()),
+        Some(KeyCheck::Mismatch { advertised }) => (
+            "Mismatch",
+            "red",
+            format!("Server advertises 0x{:?}", advertised),
+        ),
+        Some(KeyCheck::Unknown(error)) => ("Unknown", "gray", error),
+    };
+    let style = format!("relative grid items-center px-2 py-1 font-sans text-xs font-bold text-{}-900 uppercase rounded-md select-none whitespace-nowrap bg-{}-500/20", color, color);
+    view! {
+        <td class="px-4" title=title>
+            <div class=style>{label}</div>
+        </td>
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::{chain::unix_now, indexer::SnapshotCache, validator::Validator};
+        use std::{
+            collections::HashMap,
+            sync::{Arc, RwLock},
+            time::{Duration, Instant},
+        };
+
+        /// Route which threshold servers answer when they are up
+        const HEALTH_ROUTE: &str = "/healthz";
+        /// Route on which threshold servers report their version
+        const VERSION_ROUTE: &str = "/version";
+        /// Route on which threshold servers advertise their public keys as JSON
+        const INFO_ROUTE: &str = "/info";
+
+        /// The latest probe result for each validator, keyed by TSS account
+        #[derive(Clone, Default)]
+        pub struct ProbeCache(Arc<RwLock<HashMap<String, ProbeResult>>>);
+
+        impl ProbeCache {
+            pub fn get(&self, validator: &Validator) -> Option<ProbeResult> {
+                self.0
+                    .read()
+                    .ok()
+                    .and_then(|probes| probes.get(&validator.tss_account.to_string()).cloned())
+            }
+
+            fn set(&self, validator: &Validator, probe: ProbeResult) {
+                if let Ok(mut probes) = self.0.write() {
+                    probes.insert(validator.tss_account.to_string(), probe);
+                }
+            }
+        }
+
+        /// Probe the threshold server of every validator in the latest snapshot every `interval`,
+        /// waiting up to `timeout` for each to respond. Runs forever, so should be spawned.
+        pub async fn run_prober(snapshots: SnapshotCache, probes: ProbeCache, interval: Duration, timeout: Duration) {
+            let client = match reqwest::Client::builder().timeout(timeout).build() {
+                Ok(client) => client,
+                Err(error) => {
+                    log::error!("Cannot create HTTP client for probing: {}", error);
+                    return;
+                }
+            };
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                let Some(snapshot) = snapshots.get() else {
+                    continue;
+                };
+                futures::future::join_all(snapshot.validators.iter().map(|validator| async {
+                    let previous = probes.get(validator);
+                    let probe = probe(&client, validator, previous).await;
+                    if !probe.reachable {
+                        log::warn!("Threshold server {} is not responding", validator.endpoint);
+                    }
+                    if let KeyCheck::Mismatch { .. } = probe.key_check {
+                        log::warn!("Threshold server {} does not hold its registered x25519 key", validator.endpoint);
+                    }
+                    probes.set(validator, probe);
+                }))
+                .await;
+            }
+        }
+
+        /// Check a single threshold server
+        pub async fn probe(client: &reqwest::Client, validator: &Validator, previous: Option<ProbeResult>) -> ProbeResult {
+            let base_url = server_url(&validator.endpoint);
+            let started = Instant::now();
+            let health = client
+                .get(format!("{}{}", base_url, HEALTH_ROUTE))
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            let latency_ms = started.elapsed().as_millis() as u64;
+            let checked_at = unix_now();
+
+            match health {
+                Ok(_) => ProbeResult {
+                    reachable: true,
+                    latency_ms: Some(latency_ms),
+                    version: get_version(client, &base_url).await,
+                    error: None,
+                    checked_at,
+                    last_seen: Some(checked_at),
+                    key_check: check_x25519_key(client, &base_url, &validator.x25519_public_key).await,
+                },
+                Err(error) => ProbeResult {
+                    reachable: false,
+                    latency_ms: None,
+                    version: None,
+                    error: Some(error.to_string()),
+                    checked_at,
+                    last_seen: previous.as_ref().and_then(|previous| previous.last_seen),
+                    // Keep reporting a known mismatch while the server is down
+                    key_check: previous
+                        .map(|previous| previous.key_check)
+                        .unwrap_or(KeyCheck::Unknown("Server not responding".to_string())),
+                },
+            }
+        }
+
+        /// Threshold server endpoints are stored on chain without a scheme
+        pub fn server_url(endpoint: &str) -> String {
+            if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+                endpoint.trim_end_matches('/').to_string()
+            } else {
+                format!("http://{}", endpoint.trim_end_matches('/'))
+            }
+        }
+
+        async fn get_version(client: &reqwest::Client, base_url: &str) -> Option<String> {
+            let response = client
+                .get(format!("{}{}", base_url, VERSION_ROUTE))
+                .send()
+                .await
+                .ok()?
+                .error_for_status()
+                .ok()?;
+            response.text().await.ok().map(|version| version.trim().to_string())
+        }
+
+        /// Compare the key a threshold server advertises with the one registered on chain
+        async fn check_x25519_key(client: &reqwest::Client, base_url: &str, registered: &HexVec) -> KeyCheck {
+            match get_advertised_key(client, base_url).await {
+                Ok(advertised) if advertised == *registered => KeyCheck::Match,
+                Ok(advertised) => KeyCheck::Mismatch { advertised },
+                Err(error) => KeyCheck::Unknown(error.to_string()),
+            }
+        }
+
+        /// The key may be given as an array of bytes or a hex string
+        async fn get_advertised_key(client: &reqwest::Client, base_url: &str) -> anyhow::Result<HexVec> {
+            let info: serde_json::Value = client
+                .get(format!("{}{}", base_url, INFO_ROUTE))
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            let key = info
+                .get("x25519_public_key")
+                .ok_or_else(|| anyhow::anyhow!("Server does not advertise an x25519 public key"))?;
+            let bytes = match key {
+                serde_json::Value::String(hex_key) => hex::decode(hex_key.trim_start_matches("0x"))?,
+                _ => serde_json::from_value::<Vec<u8>>(key.clone())?,
+            };
+            Ok(HexVec(bytes))
+        }
+    }
+}
@@ -1,8 +1,17 @@
-use crate::{display_bytes, DisplayValue};
+use crate::{
+    display_bytes, display_hash,
+    download::{bytecode_url, configuration_interface_url},
+    indexer::{use_block, with_block, BlockRef},
+    network::{network_href, use_network},
+    DetailRow, DetailsList, DisplayValue, LinkedValue,
+};
 use leptos::*;
+use leptos_router::*;
 use serde::{Deserialize, Serialize};
+use subxt::utils::AccountId32;
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
 pub struct Program {
     pub hash: String,
     pub deployer: String,
@@ -15,10 +24,7 @@ pub struct Program {
 pub fn Program(program: Program) -> impl IntoView {
     view! {
         <tr class="hover:bg-gray-200">
-            <DisplayValue
-                value=program.hash.to_string()
-                long_value=Some(format!("{:?}", program.hash))
-            />
+            <LinkedValue value=display_hash(&program.hash) href=with_block(network_href(&format!("/program/{}", program.hash)))/>
             <DisplayValue value=program.deployer long_value=None/>
             <td class="p-4">
                 <p class="block font-sans text-sm antialiased font-normal leading-normal text-blue-gray-900">
@@ -39,42 +45,379 @@ pub fn Program(program: Program) -> impl IntoView {
     }
 }
 
+/// A summary of the contents of a WASM module
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasmSummary {
+    pub exports: Vec<String>,
+    pub imports: Vec<String>,
+    pub memories: Vec<String>,
+    pub custom_sections: Vec<String>,
+}
+
+/// A program's configuration interface, pretty printed if it is JSON
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigurationInterface {
+    pub size: usize,
+    pub json: Option<String>,
+    /// Whether the JSON looks like a JSON schema
+    pub is_schema: bool,
+}
+
+/// Everything we know about a stored program
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgramDetails {
+    pub hash: String,
+    pub deployer: String,
+    pub ref_counter: u128,
+    pub size: usize,
+    /// The parsed bytecode, or why it could not be parsed
+    pub wasm: Result<WasmSummary, String>,
+    pub configuration_interface: Option<ConfigurationInterface>,
+    /// Registered accounts which use this program
+    pub accounts: Vec<AccountId32>,
+    pub block: BlockRef,
+}
+
+#[server(GetProgramDetails, "/api")]
+pub async fn get_program_details(
+    network: String,
+    hash: String,
+    block: Option<String>,
+) -> Result<Option<ProgramDetails>, ServerFnError> {
+    use crate::{get_api_rpc, indexer::parse_hash, snapshot_at};
+
+    let program_hash = parse_hash(&hash).map_err(|e| ServerFnError::Args(format!("Invalid program hash: {}", e)))?;
+    let (api, _rpc) = get_api_rpc(&network).await?;
+    let snapshot = snapshot_at(&network, block).await?;
+
+    // Program pointers are stored in the same form as the hash is formatted here
+    let pointer = format!("{:?}", program_hash);
+    let accounts = snapshot
+        .accounts
+        .iter()
+        .filter(|account| account.program_pointers.contains(&pointer))
+        .map(|account| account.account_id.clone())
+        .collect();
+
+    query_program_details(&api, program_hash, accounts, snapshot.block.clone())
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[component]
+pub fn ProgramPage() -> impl IntoView {
+    let network = use_network();
+    let block = use_block();
+    let params = use_params_map();
+    let details = create_resource(
+        move || (network.get(), params.with(|params| params.get("hash").cloned().unwrap_or_default()), block.get()),
+        |(network, hash, block)| get_program_details(network, hash, block),
+    );
+    view! {
+        <div class="container mx-auto">
+            <A href=with_block(network_href("")) class="text-sm underline">
+                "Back to status page"
+            </A>
+            <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                {move || {
+                    details
+                        .get()
+                        .map(move |details| match details {
+                            Err(e) => {
+                                view! { <pre class="error">"server error: " {e.to_string()}</pre> }
+                                    .into_view()
+                            }
+                            Ok(None) => view! { <p class="my-4">"No program is stored with this hash."</p> }.into_view(),
+                            Ok(Some(details)) => view! { <ProgramDetails details/> }.into_view(),
+                        })
+                        .unwrap_or_default()
+                }}
+
+            </Transition>
+        </div>
+    }
+}
+
+/// Table data listing several values, one per line
+#[component]
+fn ListValue(items: Vec<String>) -> impl IntoView {
+    view! {
+        <td class="p-4">
+            {if items.is_empty() {
+                view! { <p class="text-sm text-gray-500">"None"</p> }.into_view()
+            } else {
+                view! {
+                    <ul class="font-sans text-sm text-blue-gray-900">
+                        {items.into_iter().map(|item| view! { <li><code>{item}</code></li> }).collect_view()}
+                    </ul>
+                }
+                    .into_view()
+            }}
+        </td>
+    }
+}
+
+#[component]
+pub fn ProgramDetails(details: ProgramDetails) -> impl IntoView {
+    let network = use_network().get_untracked();
+    let wasm = match details.wasm {
+        Ok(summary) => view! {
+            <DetailRow label="Exports">
+                <ListValue items=summary.exports/>
+            </DetailRow>
+            <DetailRow label="Imports">
+                <ListValue items=summary.imports/>
+            </DetailRow>
+            <DetailRow label="Memory Limits">
+                <ListValue items=summary.memories/>
+            </DetailRow>
+            <DetailRow label="Custom Sections">
+                <ListValue items=summary.custom_sections/>
+            </DetailRow>
+        }
+        .into_view(),
+        Err(error) => view! {
+            <DetailRow label="Bytecode">
+                <DisplayValue value=format!("Not a valid WASM module: {}", error) long_value=None/>
+            </DetailRow>
+        }
+        .into_view(),
+    };
+    let configurable = details.configuration_interface.is_some();
+    let configuration_interface = match details.configuration_interface {
+        None => view! { <td class="p-4 text-sm text-gray-500">"Not configurable"</td> }.into_view(),
+        Some(ConfigurationInterface { json: Some(json), is_schema, .. }) => view! {
+            <td class="p-4">
+                <p class="text-sm text-gray-500">{if is_schema { "JSON schema" } else { "JSON" }}</p>
+                <pre class="text-sm text-blue-gray-900 whitespace-pre-wrap">{json}</pre>
+            </td>
+        }
+        .into_view(),
+        Some(ConfigurationInterface { size, .. }) => view! {
+            <td class="p-4 text-sm text-blue-gray-900">
+                {format!("{} of non-JSON data", display_bytes(size as u64))}
+            </td>
+        }
+        .into_view(),
+    };
+    let accounts = details
+        .accounts
+        .into_iter()
+        .map(|account_id| {
+            view! {
+                <li>
+                    <A href=with_block(network_href(&format!("/account/{}", account_id))) class="underline">
+                        <code>{account_id.to_string()}</code>
+                    </A>
+                </li>
+            }
+        })
+        .collect_view();
+    view! {
+        <DetailsList title=format!("Program {}", details.hash)>
+            <DetailRow label="Deployer">
+                <DisplayValue value=details.deployer long_value=None/>
+            </DetailRow>
+            <DetailRow label="Times Used">
+                <DisplayValue value=details.ref_counter.to_string() long_value=None/>
+            </DetailRow>
+            <DetailRow label="Bytecode Size">
+                <DisplayValue
+                    value=format!("{} bytes ({})", details.size, display_bytes(details.size as u64))
+                    long_value=None
+                />
+            </DetailRow>
+            <DetailRow label="Download">
+                <td class="p-4 text-sm text-blue-gray-900">
+                    <a href=bytecode_url(&network, &details.hash, &details.block.hash) rel="external" download class="underline mr-4">
+                        "Bytecode"
+                    </a>
+                    {configurable
+                        .then(|| {
+                            view! {
+                                <a
+                                    href=configuration_interface_url(&network, &details.hash, &details.block.hash)
+                                    rel="external"
+                                    download
+                                    class="underline"
+                                >
+                                    "Configuration interface"
+                                </a>
+                            }
+                        })}
+                </td>
+            </DetailRow>
+            {wasm}
+            <DetailRow label="Configuration Interface">{configuration_interface}</DetailRow>
+            <DetailRow label="Used by Accounts">
+                <td class="p-4">
+                    <ul class="font-sans text-sm text-blue-gray-900">{accounts}</ul>
+                </td>
+            </DetailRow>
+            <DetailRow label="Read at Block">
+                <DisplayValue
+                    value=format!("#{}", details.block.number)
+                    long_value=Some(details.block.hash)
+                />
+            </DetailRow>
+        </DetailsList>
+    }
+}
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "ssr")] {
         use entropy_testing_utils::{
-            chain_api::entropy::runtime_types::pallet_programs::pallet::ProgramInfo,
+            chain_api::{entropy::runtime_types::pallet_programs::pallet::ProgramInfo, EntropyConfig},
         };
-        use subxt::utils::{AccountId32, H256};
+        use parity_scale_codec::Decode;
+        use subxt::{utils::H256, OnlineClient};
+        use wasmparser::{ExternalKind, MemoryType, Parser, Payload, TypeRef};
 
         impl Program {
             fn new(hash: H256, program_info: ProgramInfo<AccountId32>) -> Program {
                 Program {
-                    hash: hash.to_string(),
+                    hash: format!("{:?}", hash),
                     deployer: program_info.deployer.to_string(),
                     ref_counter: program_info.ref_counter,
                     size: program_info.bytecode.len(),
-                    // TODO: If configuration interface is json we could display it. Waiting till
-                    // we have an example of a program with a configuration interface
                     configurable: !program_info.configuration_interface.is_empty(),
                 }
             }
         }
-    }
-}
 
-#[server(GetStoredPrograms, "/api")]
-pub async fn get_stored_programs() -> Result<Vec<Program>, ServerFnError> {
-    use crate::get_api_rpc;
-    use entropy_testing_utils::test_client::get_programs;
+        /// Read all stored programs at the given block
+        pub async fn query_programs(
+            api: &OnlineClient<EntropyConfig>,
+            block_hash: H256,
+        ) -> anyhow::Result<Vec<Program>> {
+            let keys = Vec::<()>::new();
+            let storage_address = subxt::dynamic::storage("Programs", "Programs", keys);
+            let mut iter = api.storage().at(block_hash).iter(storage_address).await?;
+            let mut programs = Vec::new();
+            while let Some(item) = iter.next().await {
+                let (storage_key, program) = item?;
+                let decoded = program.into_encoded();
+                let program_info = ProgramInfo::<AccountId32>::decode(&mut decoded.as_ref())?;
+                let hash: [u8; 32] = storage_key[storage_key.len() - 32..].try_into()?;
+                programs.push(Program::new(H256(hash), program_info))
+            }
+            Ok(programs)
+        }
 
-    let (api, rpc) = get_api_rpc().await?;
+        /// Read a single program at the given block
+        pub async fn query_program_details(
+            api: &OnlineClient<EntropyConfig>,
+            program_hash: H256,
+            accounts: Vec<AccountId32>,
+            block: BlockRef,
+        ) -> anyhow::Result<Option<ProgramDetails>> {
+            let program_info = match fetch_program_info(api, block.block_hash()?, program_hash).await? {
+                Some(program_info) => program_info,
+                None => return Ok(None),
+            };
 
-    let programs = get_programs(&api, &rpc)
-        .await
-        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
-        .into_iter()
-        .map(|(hash, program_info)| Program::new(hash, program_info))
-        .collect();
+            Ok(Some(ProgramDetails {
+                hash: format!("{:?}", program_hash),
+                deployer: program_info.deployer.to_string(),
+                ref_counter: program_info.ref_counter,
+                size: program_info.bytecode.len(),
+                wasm: summarize_wasm(&program_info.bytecode).map_err(|e| e.to_string()),
+                configuration_interface: describe_configuration_interface(&program_info.configuration_interface),
+                accounts,
+                block,
+            }))
+        }
 
-    Ok(programs)
+        /// Read the raw stored program with the given hash
+        pub async fn fetch_program_info(
+            api: &OnlineClient<EntropyConfig>,
+            block_hash: H256,
+            program_hash: H256,
+        ) -> anyhow::Result<Option<ProgramInfo<AccountId32>>> {
+            let storage_address = subxt::dynamic::storage("Programs", "Programs", vec![program_hash]);
+            match api.storage().at(block_hash).fetch(&storage_address).await? {
+                Some(program_info) => Ok(Some(ProgramInfo::decode(&mut program_info.into_encoded().as_ref())?)),
+                None => Ok(None),
+            }
+        }
+
+        /// List the exports, imports, memories and custom sections of a WASM module
+        pub fn summarize_wasm(bytecode: &[u8]) -> anyhow::Result<WasmSummary> {
+            let mut summary = WasmSummary::default();
+            for payload in Parser::new(0).parse_all(bytecode) {
+                match payload? {
+                    Payload::ImportSection(reader) => {
+                        for import in reader {
+                            let import = import?;
+                            let kind = match import.ty {
+                                TypeRef::Func(_) => "function",
+                                TypeRef::Table(_) => "table",
+                                TypeRef::Memory(memory) => {
+                                    summary.memories.push(describe_memory(&memory));
+                                    "memory"
+                                }
+                                TypeRef::Global(_) => "global",
+                                TypeRef::Tag(_) => "tag",
+                            };
+                            summary.imports.push(format!("{}::{} ({})", import.module, import.name, kind));
+                        }
+                    }
+                    Payload::MemorySection(reader) => {
+                        for memory in reader {
+                            summary.memories.push(describe_memory(&memory?));
+                        }
+                    }
+                    Payload::ExportSection(reader) => {
+                        for export in reader {
+                            let export = export?;
+                            let kind = match export.kind {
+                                ExternalKind::Func => "function",
+                                ExternalKind::Table => "table",
+                                ExternalKind::Memory => "memory",
+                                ExternalKind::Global => "global",
+                                ExternalKind::Tag => "tag",
+                            };
+                            summary.exports.push(format!("{} ({})", export.name, kind));
+                        }
+                    }
+                    Payload::CustomSection(reader) => {
+                        summary
+                            .custom_sections
+                            .push(format!("{} ({})", reader.name(), display_bytes(reader.data().len() as u64)));
+                    }
+                    _ => {}
+                }
+            }
+            Ok(summary)
+        }
+
+        /// Describe the size limits of a memory in 64KiB pages
+        fn describe_memory(memory: &MemoryType) -> String {
+            match memory.maximum {
+                Some(maximum) => format!("{} - {} pages", memory.initial, maximum),
+                None => format!("{} pages minimum, no maximum", memory.initial),
+            }
+        }
+
+        fn describe_configuration_interface(configuration_interface: &[u8]) -> Option<ConfigurationInterface> {
+            if configuration_interface.is_empty() {
+                return None;
+            }
+            let json = serde_json::from_slice::<serde_json::Value>(configuration_interface).ok();
+            Some(ConfigurationInterface {
+                size: configuration_interface.len(),
+                is_schema: json
+                    .as_ref()
+                    .and_then(|json| json.as_object())
+                    .map(|object| object.contains_key("$schema") || object.contains_key("properties"))
+                    .unwrap_or(false),
+                json: json.and_then(|json| serde_json::to_string_pretty(&json).ok()),
+            })
+        }
+    }
+}
+
+#[server(GetStoredPrograms, "/api")]
+pub async fn get_stored_programs(network: String, block: Option<String>) -> Result<Vec<Program>, ServerFnError> {
+    Ok(crate::snapshot_at(&network, block).await?.programs.clone())
 }
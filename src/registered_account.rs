@@ -1,15 +1,21 @@
-use crate::{DisplayValue, HexVec};
+use crate::{display_hash, indexer::with_block, network::network_href, DisplayValue, HexVec, LinkedValue};
 use ethers_core::abi::ethabi::ethereum_types::H160;
 use leptos::*;
+use leptos_router::A;
 use serde::{Deserialize, Serialize};
 use subxt::utils::AccountId32;
 
 pub use synedrion::{k256::ecdsa::VerifyingKey, KeyShare};
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
 pub struct RegisteredAccount {
+    #[cfg_attr(feature = "ssr", schema(value_type = String))]
     pub account_id: AccountId32,
+    /// Access mode, followed by the colour it is shown in
+    #[cfg_attr(feature = "ssr", schema(value_type = Vec<String>))]
     pub key_visibility: (String, String),
     pub verifying_key: HexVec,
+    #[cfg_attr(feature = "ssr", schema(value_type = Option<String>))]
     pub ethereum_address: Option<H160>,
     pub program_pointers: Vec<String>,
     pub program_modification_account: String,
@@ -19,7 +25,10 @@ pub struct RegisteredAccount {
 pub fn RegisteredAccount(account: RegisteredAccount) -> impl IntoView {
     view! {
         <tr class="hover:bg-gray-200">
-            <DisplayValue value=account.account_id.to_string() long_value=None/>
+            <LinkedValue
+                value=account.account_id.to_string()
+                href=with_block(network_href(&format!("/account/{}", account.account_id)))
+            />
             <KeyVisibility key_visibility=account.key_visibility.0 color=account.key_visibility.1/>
             <DisplayValue value=account.program_modification_account long_value=None/>
             <DisplayValue
@@ -32,13 +41,33 @@ pub fn RegisteredAccount(account: RegisteredAccount) -> impl IntoView {
             />
             <td class="p-4">
                 <p class="block font-sans text-sm antialiased font-normal leading-normal text-blue-gray-900">
-                    {account.program_pointers}
+                    {account
+                        .program_pointers
+                        .into_iter()
+                        .map(|program_pointer| {
+                            view! {
+                                <A href=with_block(network_href(&format!("/program/{}", program_pointer))) class="underline mr-2">
+                                    <code>{display_hash(&program_pointer)}</code>
+                                </A>
+                            }
+                        })
+                        .collect_view()}
                 </p>
             </td>
         </tr>
     }
 }
 
+/// Colour a key visibility is shown in
+pub fn key_visibility_color(key_visibility: &str) -> &'static str {
+    match key_visibility {
+        "Public" => "green",
+        "Permissioned" => "amber",
+        "Private" => "red",
+        _ => "gray",
+    }
+}
+
 #[component]
 pub fn KeyVisibility(key_visibility: String, color: String) -> impl IntoView {
     let style = format!("relative grid items-center px-2 py-1 font-sans text-xs font-bold text-{}-900 uppercase rounded-md select-none whitespace-nowrap bg-{}-500/20", &color, &color);
@@ -52,9 +81,11 @@ pub fn KeyVisibility(key_visibility: String, color: String) -> impl IntoView {
 cfg_if::cfg_if! {
     if #[cfg(feature = "ssr")] {
         use entropy_testing_utils::{
-            chain_api::entropy::runtime_types::pallet_relayer::pallet::RegisteredInfo,
+            chain_api::{entropy::runtime_types::pallet_relayer::pallet::RegisteredInfo, EntropyConfig},
         };
         use entropy_shared::KeyVisibility;
+        use parity_scale_codec::Decode;
+        use subxt::{utils::H256, OnlineClient};
         use ethers_core::utils::raw_public_key_to_address;
         use synedrion::k256::{
             // ecdsa::{RecoveryId, Signature as k256Signature, VerifyingKey},
@@ -64,16 +95,17 @@ cfg_if::cfg_if! {
 
         impl RegisteredAccount {
             fn new(account_id: AccountId32, registered_info: RegisteredInfo) -> RegisteredAccount {
+                let key_visibility = match registered_info.key_visibility.0 {
+                    KeyVisibility::Public => "Public",
+                    KeyVisibility::Permissioned => "Permissioned",
+                    KeyVisibility::Private(_) => "Private",
+                };
                 RegisteredAccount {
                     account_id,
-                    key_visibility: match registered_info.key_visibility.0 {
-                        KeyVisibility::Public => ("Public".to_string(), "green".to_string()),
-                        KeyVisibility::Permissioned => ("Permissioned".to_string(), "amber".to_string()),
-                        KeyVisibility::Private(_) => ("Private".to_string(), "red".to_string()),
-                    },
+                    key_visibility: (key_visibility.to_string(), key_visibility_color(key_visibility).to_string()),
                     verifying_key: HexVec(registered_info.verifying_key.0.clone()),
                     ethereum_address: public_key_to_eth_address(registered_info.verifying_key.0).ok(),
-                    program_pointers: registered_info.programs_data.0.into_iter().map(|program_instance| format!("{}", program_instance.program_pointer)).collect(),
+                    program_pointers: registered_info.programs_data.0.into_iter().map(|program_instance| format!("{:?}", program_instance.program_pointer)).collect(),
                     program_modification_account: registered_info.program_modification_account.to_string(),
                 }
             }
@@ -81,27 +113,39 @@ cfg_if::cfg_if! {
 
         /// Convert a compressed verifying key to an ethereum address
         pub fn public_key_to_eth_address(compressed_public_key: Vec<u8>) -> anyhow::Result<H160> {
-            let encoded_point = EncodedPoint::<Secp256k1>::from_bytes(&compressed_public_key)?;
+            let uncompressed = uncompress_public_key(&compressed_public_key)?;
+            Ok(raw_public_key_to_address(&uncompressed[1..]))
+        }
+
+        /// Convert a compressed verifying key to its SEC1 uncompressed form
+        pub fn uncompress_public_key(compressed_public_key: &[u8]) -> anyhow::Result<Vec<u8>> {
+            let encoded_point = EncodedPoint::<Secp256k1>::from_bytes(compressed_public_key)?;
             let verifying_key = VerifyingKey::from_encoded_point(&encoded_point)?;
-            let encoded = verifying_key.to_encoded_point(false);
-            Ok(raw_public_key_to_address(&encoded.as_bytes()[1..]))
+            Ok(verifying_key.to_encoded_point(false).as_bytes().to_vec())
+        }
+
+        /// Read all registered accounts at the given block
+        pub async fn query_accounts(
+            api: &OnlineClient<EntropyConfig>,
+            block_hash: H256,
+        ) -> anyhow::Result<Vec<RegisteredAccount>> {
+            let keys = Vec::<()>::new();
+            let storage_address = subxt::dynamic::storage("Relayer", "Registered", keys);
+            let mut iter = api.storage().at(block_hash).iter(storage_address).await?;
+            let mut accounts = Vec::new();
+            while let Some(item) = iter.next().await {
+                let (storage_key, account) = item?;
+                let decoded = account.into_encoded();
+                let registered_info = RegisteredInfo::decode(&mut decoded.as_ref())?;
+                let key: [u8; 32] = storage_key[storage_key.len() - 32..].try_into()?;
+                accounts.push(RegisteredAccount::new(AccountId32(key), registered_info))
+            }
+            Ok(accounts)
         }
     }
 }
 
 #[server(GetRegisteredAccounts, "/api")]
-pub async fn get_registered_accounts() -> Result<Vec<RegisteredAccount>, ServerFnError> {
-    use crate::get_api_rpc;
-    use entropy_testing_utils::test_client::get_accounts;
-
-    let (api, rpc) = get_api_rpc().await?;
-
-    let accounts = get_accounts(&api, &rpc)
-        .await
-        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
-        .into_iter()
-        .map(|(account_id, registered_info)| RegisteredAccount::new(account_id, registered_info))
-        .collect();
-
-    Ok(accounts)
+pub async fn get_registered_accounts(network: String, block: Option<String>) -> Result<Vec<RegisteredAccount>, ServerFnError> {
+    Ok(crate::snapshot_at(&network, block).await?.accounts.clone())
 }
@@ -0,0 +1,288 @@
+use crate::{
+    display_duration, display_timestamp,
+    indexer::with_block,
+    network::network_href,
+    registered_account::{key_visibility_color, KeyVisibility},
+    DisplayValue, LinkedValue,
+};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use subxt::utils::AccountId32;
+
+/// An account which has asked to register and is waiting for the validators to confirm it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingRegistration {
+    pub account_id: AccountId32,
+    pub key_visibility: Option<String>,
+    /// Signing subgroups which have confirmed the registration
+    pub confirmations: usize,
+    /// Confirmations needed for the registration to complete
+    pub required_confirmations: Option<u32>,
+    /// Block at which the registration was first seen
+    pub since_block: Option<u32>,
+    /// Whether `since_block` is the block the registration was requested in, rather than when
+    /// the indexer started following the chain
+    pub since_request: bool,
+    /// Seconds from the block the registration was first seen in to the block of the snapshot,
+    /// by their timestamps
+    pub waiting: Option<u64>,
+}
+
+/// How a registration ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RegistrationResult {
+    Registered,
+    Failed,
+    Cancelled,
+}
+
+impl RegistrationResult {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RegistrationResult::Registered => "Registered",
+            RegistrationResult::Failed => "Failed",
+            RegistrationResult::Cancelled => "Cancelled",
+        }
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            RegistrationResult::Registered => "green",
+            RegistrationResult::Failed => "red",
+            RegistrationResult::Cancelled => "gray",
+        }
+    }
+}
+
+/// A registration which recently ended, from the relayer pallet events
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistrationOutcome {
+    pub account_id: AccountId32,
+    pub result: RegistrationResult,
+    pub block: u32,
+    /// Unix time in seconds of the block it ended in
+    pub time: u64,
+    /// Seconds from the block it was requested in to the block it ended in, if the request was
+    /// seen
+    pub took: Option<u64>,
+}
+
+#[component]
+pub fn PendingRegistration(registration: PendingRegistration) -> impl IntoView {
+    let key_visibility = registration.key_visibility.unwrap_or_else(|| "Unknown".to_string());
+    let color = key_visibility_color(&key_visibility).to_string();
+    let confirmations = match registration.required_confirmations {
+        Some(required) => format!("{} of {}", registration.confirmations, required),
+        None => registration.confirmations.to_string(),
+    };
+    let prefix = if registration.since_request { "" } else { "Over " };
+    let waiting = registration
+        .waiting
+        .map(|waiting| format!("{}{}", prefix, display_duration(waiting)))
+        .unwrap_or_default();
+    let since = registration
+        .since_block
+        .map(|block| format!("{}#{}", prefix.to_lowercase(), block))
+        .unwrap_or_default();
+    view! {
+        <tr class="hover:bg-gray-200">
+            <DisplayValue value=registration.account_id.to_string() long_value=None/>
+            <KeyVisibility key_visibility color/>
+            <DisplayValue value=confirmations long_value=None/>
+            <DisplayValue value=since long_value=None/>
+            <DisplayValue value=waiting long_value=None/>
+        </tr>
+    }
+}
+
+#[component]
+pub fn RegistrationResultBadge(result: RegistrationResult) -> impl IntoView {
+    let color = result.color();
+    let style = format!("relative grid items-center px-2 py-1 font-sans text-xs font-bold text-{}-900 uppercase rounded-md select-none whitespace-nowrap bg-{}-500/20", color, color);
+    view! {
+        <td class="px-4">
+            <div class=style>{result.label()}</div>
+        </td>
+    }
+}
+
+#[component]
+pub fn RegistrationOutcome(outcome: RegistrationOutcome) -> impl IntoView {
+    view! {
+        <tr class="hover:bg-gray-200">
+            {if outcome.result == RegistrationResult::Registered {
+                view! {
+                    <LinkedValue
+                        value=outcome.account_id.to_string()
+                        href=with_block(network_href(&format!("/account/{}", outcome.account_id)))
+                    />
+                }
+                    .into_view()
+            } else {
+                view! { <DisplayValue value=outcome.account_id.to_string() long_value=None/> }.into_view()
+            }}
+            <RegistrationResultBadge result=outcome.result/>
+            <DisplayValue value=format!("#{}", outcome.block) long_value=Some(display_timestamp(outcome.time))/>
+            <DisplayValue value=outcome.took.map(display_duration).unwrap_or_default() long_value=None/>
+        </tr>
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::indexer::Snapshot;
+        use entropy_testing_utils::chain_api::EntropyConfig;
+        use std::collections::{HashMap, VecDeque};
+        use subxt::{
+            blocks::Block,
+            ext::scale_value::{At, Value, ValueDef},
+            utils::H256,
+            OnlineClient,
+        };
+
+        /// How many ended registrations are kept
+        const RECENT_OUTCOMES: usize = 20;
+
+        /// Read the registrations waiting for confirmation at the given block
+        pub async fn query_pending_registrations(
+            api: &OnlineClient<EntropyConfig>,
+            block_hash: H256,
+        ) -> anyhow::Result<Vec<PendingRegistration>> {
+            let required_confirmations = api
+                .constants()
+                .at(&subxt::dynamic::constant("Relayer", "SigningPartySize"))
+                .ok()
+                .and_then(|constant| constant.to_value().ok())
+                .and_then(|value| value.as_u128())
+                .map(|size| size as u32);
+
+            let keys = Vec::<()>::new();
+            let storage_address = subxt::dynamic::storage("Relayer", "Registering", keys);
+            let mut iter = api.storage().at(block_hash).iter(storage_address).await?;
+            let mut registrations = Vec::new();
+            while let Some(item) = iter.next().await {
+                let (storage_key, details) = item?;
+                let details = details.to_value()?;
+                let key: [u8; 32] = storage_key[storage_key.len() - 32..].try_into()?;
+                registrations.push(PendingRegistration {
+                    account_id: AccountId32(key),
+                    key_visibility: details.at("key_visibility").and_then(variant_name),
+                    confirmations: details.at("confirmations").map(sequence_length).unwrap_or_default(),
+                    required_confirmations,
+                    since_block: None,
+                    since_request: false,
+                    waiting: None,
+                });
+            }
+            Ok(registrations)
+        }
+
+        /// The name of an enum variant, looking through newtype wrappers
+        fn variant_name<T>(value: &Value<T>) -> Option<String> {
+            match &value.value {
+                ValueDef::Variant(variant) => Some(variant.name.clone()),
+                ValueDef::Composite(composite) if composite.len() == 1 => variant_name(composite.values().next()?),
+                _ => None,
+            }
+        }
+
+        fn sequence_length<T>(value: &Value<T>) -> usize {
+            match &value.value {
+                ValueDef::Composite(composite) => composite.len(),
+                _ => 0,
+            }
+        }
+
+        /// Follows registrations from the relayer pallet events of each finalized block, to know
+        /// when each pending registration was requested and how recent ones ended
+        #[derive(Default)]
+        pub struct RegistrationTracker {
+            /// Block number and unix time in seconds of the block each registration was first seen
+            /// in, and whether that was when it was requested
+            started: HashMap<AccountId32, (u32, u64, bool)>,
+            recent: VecDeque<RegistrationOutcome>,
+            /// Unix time in seconds of the last block observed
+            time: Option<u64>,
+        }
+
+        impl RegistrationTracker {
+            /// Record the registrations requested and ended in a block, given the unix time in
+            /// seconds of the block
+            pub async fn observe(
+                &mut self,
+                block: &Block<EntropyConfig, OnlineClient<EntropyConfig>>,
+                now: u64,
+            ) -> anyhow::Result<()> {
+                self.time = Some(now);
+                for event in block.events().await?.iter() {
+                    let event = event?;
+                    if event.pallet_name() != "Relayer" {
+                        continue;
+                    }
+                    // Each registration event starts with the account being registered
+                    let Some(account_id) = event
+                        .field_bytes()
+                        .get(..32)
+                        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                        .map(AccountId32)
+                    else {
+                        continue;
+                    };
+                    let result = match event.variant_name() {
+                        "SignalRegister" => {
+                            self.started.insert(account_id, (block.number(), now, true));
+                            continue;
+                        }
+                        "AccountRegistered" => RegistrationResult::Registered,
+                        "FailedRegistration" => RegistrationResult::Failed,
+                        "RegistrationCancelled" => RegistrationResult::Cancelled,
+                        _ => continue,
+                    };
+                    let took = self
+                        .started
+                        .remove(&account_id)
+                        .filter(|(_, _, since_request)| *since_request)
+                        .map(|(_, started_at, _)| now.saturating_sub(started_at));
+                    log::info!("Registration of {} {}", account_id, result.label().to_lowercase());
+                    self.recent.push_front(RegistrationOutcome {
+                        account_id,
+                        result,
+                        block: block.number(),
+                        time: now,
+                        took,
+                    });
+                    self.recent.truncate(RECENT_OUTCOMES);
+                }
+                Ok(())
+            }
+
+            /// Add when each pending registration started, and the recently ended ones, to a
+            /// snapshot of the last block observed
+            pub fn annotate(&mut self, snapshot: &mut Snapshot) {
+                let block = snapshot.block.number;
+                // Anything pending before the indexer started has been waiting at least this long
+                if let Some(now) = self.time {
+                    for registration in &snapshot.pending_registrations {
+                        self.started
+                            .entry(registration.account_id.clone())
+                            .or_insert((block, now, false));
+                    }
+                }
+                self.started.retain(|account_id, _| {
+                    snapshot
+                        .pending_registrations
+                        .iter()
+                        .any(|registration| &registration.account_id == account_id)
+                });
+                for registration in snapshot.pending_registrations.iter_mut() {
+                    if let Some((since_block, since, since_request)) = self.started.get(&registration.account_id) {
+                        registration.since_block = Some(*since_block);
+                        registration.since_request = *since_request;
+                        registration.waiting = self.time.map(|now| now.saturating_sub(*since));
+                    }
+                }
+                snapshot.recent_registrations = self.recent.iter().cloned().collect();
+            }
+        }
+    }
+}
@@ -0,0 +1,546 @@
+use crate::{
+    display_hash, display_timestamp,
+    history::{HistoryRange, LineChart},
+    network::{network_href, use_network},
+    DetailsTable, DisplayValue, LinkedValue,
+};
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+/// An on-chain event recording a signature request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureEvent {
+    /// Unix time in seconds of the block, from its timestamp
+    pub time: u64,
+    pub block: u32,
+    /// The registered account the signature is for, or failing that who submitted it
+    pub account_id: Option<String>,
+    pub pallet: String,
+    pub event: String,
+    /// Programs of the account in the latest indexed snapshot when the event was recorded, which
+    /// may be a few blocks after the event
+    pub programs: Vec<String>,
+}
+
+/// Number of signature events for one account or program
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigningCount {
+    pub key: String,
+    pub count: u64,
+}
+
+/// Signing activity over a time range
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SigningActivity {
+    pub total: u64,
+    /// Number of signature events in each interval, by the unix time in seconds it starts at
+    pub volume: Vec<(u64, f64)>,
+    /// Accounts with the most signature events, most first
+    pub accounts: Vec<SigningCount>,
+    /// Programs with the most signature events, most first
+    pub programs: Vec<SigningCount>,
+    /// Latest signature events, newest first
+    pub recent: Vec<SignatureEvent>,
+    /// Configured events which the runtime does not have, so are never recorded
+    pub missing_events: Vec<String>,
+}
+
+/// Signing activity within the given range, optionally only for one account
+#[server(GetSigningActivity, "/api")]
+pub async fn get_signing_activity(
+    network: String,
+    range: HistoryRange,
+    account: Option<String>,
+) -> Result<SigningActivity, ServerFnError> {
+    crate::signing_store(&network)?
+        .activity(range, account)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Feed and counts of signature requests, optionally for the account given as `?account=`
+#[component]
+pub fn SigningPage() -> impl IntoView {
+    let network = use_network();
+    let query = use_query_map();
+    let account = create_memo(move |_| {
+        query.with(|query| query.get("account").cloned().filter(|account| !account.is_empty()))
+    });
+    let (range, set_range) = create_signal(HistoryRange::Day);
+    let activity = create_resource(
+        move || (network.get(), range.get(), account.get()),
+        |(network, range, account)| get_signing_activity(network, range, account),
+    );
+    view! {
+        <div class="container mx-auto">
+            <A href=network_href("") class="text-sm underline">
+                "Back to status page"
+            </A>
+            <div class="flex items-center justify-between">
+                <h1 class="text-2xl my-4 break-all">
+                    {move || match account.get() {
+                        Some(account) => format!("Signing Activity of {}", account),
+                        None => "Signing Activity".to_string(),
+                    }}
+                </h1>
+                <div class="flex gap-2">
+                    {HistoryRange::ALL
+                        .into_iter()
+                        .map(|option| {
+                            view! {
+                                <button
+                                    class="px-2 py-1 text-sm rounded-md"
+                                    class:bg-blue-50=move || range.get() == option
+                                    on:click=move |_| set_range.set(option)
+                                >
+                                    {option.label()}
+                                </button>
+                            }
+                        })
+                        .collect_view()}
+                </div>
+            </div>
+            <Show when=move || account.with(Option::is_some) fallback=|| ()>
+                <A href=network_href("/signing") class="text-sm underline">
+                    "Show all accounts"
+                </A>
+            </Show>
+            <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                {move || {
+                    activity
+                        .get()
+                        .map(|activity| match activity {
+                            Err(e) => {
+                                view! { <pre class="error">"server error: " {e.to_string()}</pre> }
+                                    .into_view()
+                            }
+                            Ok(activity) => view! { <SigningActivityView activity/> }.into_view(),
+                        })
+                        .unwrap_or_default()
+                }}
+
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+fn SigningActivityView(activity: SigningActivity) -> impl IntoView {
+    let SigningActivity { total, volume, accounts, programs, recent, missing_events } = activity;
+    let counts = |counts: Vec<SigningCount>, path: &'static str| {
+        if counts.is_empty() {
+            return view! {
+                <tr>
+                    <td class="p-4">"No signature requests seen."</td>
+                </tr>
+            }
+                .into_view();
+        }
+        counts
+            .into_iter()
+            .map(|count| {
+                let value = if path == "program" { display_hash(&count.key) } else { count.key.clone() };
+                let href = match path {
+                    "account" => network_href(&format!("/signing?account={}", count.key)),
+                    _ => network_href(&format!("/{}/{}", path, count.key)),
+                };
+                view! {
+                    <tr class="hover:bg-gray-200">
+                        <LinkedValue value href/>
+                        <DisplayValue value=count.count.to_string() long_value=None/>
+                    </tr>
+                }
+            })
+            .collect_view()
+    };
+    let recent = if recent.is_empty() {
+        view! {
+            <tr>
+                <td class="p-4">"No signature requests seen."</td>
+            </tr>
+        }
+            .into_view()
+    } else {
+        recent.into_iter().map(|event| view! { <SignatureEventRow event/> }).collect_view()
+    };
+    let missing_events = (!missing_events.is_empty()).then(|| {
+        view! {
+            <div class="my-4 p-4 rounded-xl bg-amber-500/20 text-amber-900">
+                <p class="font-bold">"Some signing events are not being recorded"</p>
+                <p class="text-sm">
+                    {format!(
+                        "The runtime has no {} event, so signature requests recorded by it are missing here. The events are set with signing.events in the config file.",
+                        missing_events.join(" or "),
+                    )}
+                </p>
+            </div>
+        }
+    });
+    view! {
+        {missing_events}
+        <p class="text-sm text-blue-gray-900">{format!("{} signature requests", total)}</p>
+        <div class="my-4">
+            <LineChart title="Signature Requests" points=volume display=|value| format!("{:.0}", value)/>
+        </div>
+        <div class="grid grid-cols-1 md:grid-cols-2 gap-4">
+            <div>
+                <DetailsTable title="By Account" headings=vec!["Account ID", "Requests"]>
+                    {counts(accounts, "account")}
+                </DetailsTable>
+            </div>
+            <div>
+                <DetailsTable title="By Program" headings=vec!["Program", "Requests"]>
+                    {counts(programs, "program")}
+                </DetailsTable>
+            </div>
+        </div>
+        <DetailsTable title="Recent Signature Requests" headings=vec!["Time", "Block", "Account ID", "Event", "Programs"]>
+            {recent}
+        </DetailsTable>
+    }
+}
+
+#[component]
+fn SignatureEventRow(event: SignatureEvent) -> impl IntoView {
+    view! {
+        <tr class="hover:bg-gray-200">
+            <DisplayValue value=display_timestamp(event.time) long_value=None/>
+            <LinkedValue value=format!("#{}", event.block) href=network_href(&format!("/block/{}", event.block))/>
+            {match event.account_id {
+                Some(account_id) => {
+                    view! {
+                        <LinkedValue
+                            value=account_id.clone()
+                            href=network_href(&format!("/account/{}", account_id))
+                        />
+                    }
+                        .into_view()
+                }
+                None => view! { <DisplayValue value="Unknown".to_string() long_value=None/> }.into_view(),
+            }}
+            <DisplayValue value=format!("{}.{}", event.pallet, event.event) long_value=None/>
+            <td class="p-4 text-sm">
+                {event
+                    .programs
+                    .into_iter()
+                    .map(|program| {
+                        view! {
+                            <A href=network_href(&format!("/program/{}", program)) class="underline mr-2">
+                                <code>{display_hash(&program)}</code>
+                            </A>
+                        }
+                    })
+                    .collect_view()}
+            </td>
+        </tr>
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::{
+            blocks::decode_signer,
+            chain::unix_now,
+            database::Database,
+            indexer::SnapshotCache,
+        };
+        use entropy_testing_utils::chain_api::EntropyConfig;
+        use rusqlite::params;
+        use std::{
+            collections::HashMap,
+            sync::{Arc, RwLock},
+            time::Duration,
+        };
+        use subxt::{blocks::Block, events::Phase, Metadata, OnlineClient};
+
+        /// Old signature events are deleted after this many blocks
+        const PRUNE_EVERY_BLOCKS: u32 = 600;
+
+        /// Number of intervals in the volume chart
+        const VOLUME_POINTS: u64 = 48;
+
+        /// How many accounts, programs and recent events are returned
+        const TOP_COUNT: usize = 20;
+        const RECENT_COUNT: usize = 50;
+
+        const SCHEMA: &str = "
+            CREATE TABLE IF NOT EXISTS signatures (
+                network TEXT NOT NULL,
+                time INTEGER NOT NULL,
+                block INTEGER NOT NULL,
+                account_id TEXT,
+                pallet TEXT NOT NULL,
+                event TEXT NOT NULL,
+                programs TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS signatures_network_time ON signatures (network, time);
+        ";
+
+        /// Signature events seen on one network, kept in the local database
+        #[derive(Clone)]
+        pub struct SigningStore {
+            database: Database,
+            network: String,
+            retention: Duration,
+            /// Configured events which the runtime does not have, once checked
+            missing_events: Arc<RwLock<Vec<String>>>,
+        }
+
+        impl SigningStore {
+            pub fn open(database: Database, network: &str, retention: Duration) -> anyhow::Result<Self> {
+                database.create_tables(SCHEMA)?;
+                Ok(SigningStore {
+                    database,
+                    network: network.to_string(),
+                    retention,
+                    missing_events: Default::default(),
+                })
+            }
+
+            fn set_missing_events(&self, events: Vec<String>) {
+                if let Ok(mut missing_events) = self.missing_events.write() {
+                    *missing_events = events;
+                }
+            }
+
+            async fn record(&self, events: Vec<SignatureEvent>) -> anyhow::Result<()> {
+                let network = self.network.clone();
+                self.database.with_connection(move |connection| {
+                    let transaction = connection.unchecked_transaction()?;
+                    for event in &events {
+                        transaction.execute(
+                            "INSERT INTO signatures VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                            params![
+                                network,
+                                event.time,
+                                event.block,
+                                event.account_id,
+                                event.pallet,
+                                event.event,
+                                event.programs.join(","),
+                            ],
+                        )?;
+                    }
+                    transaction.commit()
+                })
+                .await
+            }
+
+            /// Delete events older than the retention period
+            async fn prune(&self) -> anyhow::Result<()> {
+                let cutoff = unix_now().saturating_sub(self.retention.as_secs());
+                let network = self.network.clone();
+                self.database.with_connection(move |connection| {
+                    connection.execute("DELETE FROM signatures WHERE network = ?1 AND time < ?2", params![network, cutoff])?;
+                    Ok(())
+                })
+                .await
+            }
+
+            /// Counts, volume and the latest events within the given range
+            pub async fn activity(&self, range: HistoryRange, account: Option<String>) -> anyhow::Result<SigningActivity> {
+                let now = unix_now();
+                let since = now.saturating_sub(range.seconds());
+                let interval = (range.seconds() / VOLUME_POINTS).max(1);
+                let network = self.network.clone();
+                let missing_events = self.missing_events.read().map(|events| events.clone()).unwrap_or_default();
+                self.database.with_connection(move |connection| {
+                    // Every query is over the same events, optionally of one account
+                    let filter = "network = ?1 AND time >= ?2 AND (?3 IS NULL OR account_id = ?3)";
+
+                    let mut buckets: HashMap<u64, u64> = HashMap::new();
+                    let mut programs: HashMap<String, u64> = HashMap::new();
+                    let mut statement = connection.prepare(&format!("SELECT time, programs FROM signatures WHERE {}", filter))?;
+                    let mut rows = statement.query(params![network, since, account])?;
+                    let mut total = 0;
+                    while let Some(row) = rows.next()? {
+                        let time: u64 = row.get(0)?;
+                        let event_programs: String = row.get(1)?;
+                        total += 1;
+                        *buckets.entry(time / interval).or_default() += 1;
+                        for program in event_programs.split(',').filter(|program| !program.is_empty()) {
+                            *programs.entry(program.to_string()).or_default() += 1;
+                        }
+                    }
+                    // Intervals without any events are shown as zero rather than left out
+                    let volume = (since / interval..=now / interval)
+                        .map(|bucket| (bucket * interval, buckets.get(&bucket).copied().unwrap_or(0) as f64))
+                        .collect();
+                    let mut programs: Vec<SigningCount> = programs
+                        .into_iter()
+                        .map(|(key, count)| SigningCount { key, count })
+                        .collect();
+                    programs.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+                    programs.truncate(TOP_COUNT);
+
+                    let accounts = connection
+                        .prepare(&format!(
+                            "SELECT account_id, COUNT(*) FROM signatures WHERE {} AND account_id IS NOT NULL
+                            GROUP BY account_id ORDER BY 2 DESC, 1 LIMIT ?4",
+                            filter
+                        ))?
+                        .query_map(params![network, since, account, TOP_COUNT], |row| {
+                            Ok(SigningCount {
+                                key: row.get(0)?,
+                                count: row.get(1)?,
+                            })
+                        })?
+                        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+                    let recent = connection
+                        .prepare(&format!(
+                            "SELECT time, block, account_id, pallet, event, programs FROM signatures WHERE {}
+                            ORDER BY time DESC, block DESC LIMIT ?4",
+                            filter
+                        ))?
+                        .query_map(params![network, since, account, RECENT_COUNT], |row| {
+                            let programs: String = row.get(5)?;
+                            Ok(SignatureEvent {
+                                time: row.get(0)?,
+                                block: row.get(1)?,
+                                account_id: row.get(2)?,
+                                pallet: row.get(3)?,
+                                event: row.get(4)?,
+                                programs: programs
+                                    .split(',')
+                                    .filter(|program| !program.is_empty())
+                                    .map(str::to_string)
+                                    .collect(),
+                            })
+                        })?
+                        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+                    Ok(SigningActivity {
+                        total,
+                        volume,
+                        accounts,
+                        programs,
+                        recent,
+                        missing_events,
+                    })
+                })
+                .await
+            }
+        }
+
+        /// Records the signature events given as `Pallet.Event` from each finalized block the
+        /// indexer follows
+        #[derive(Clone)]
+        pub struct SigningMonitor {
+            store: SigningStore,
+            events: Vec<String>,
+        }
+
+        impl SigningMonitor {
+            pub fn new(store: SigningStore, events: Vec<String>) -> Self {
+                SigningMonitor { store, events }
+            }
+
+            /// Find the configured events which the runtime does not have, as they would never be
+            /// seen. Each is logged as an error and shown on the signing activity page.
+            pub fn check_events(&self, metadata: &Metadata) {
+                let missing: Vec<String> = self
+                    .events
+                    .iter()
+                    .filter(|event| {
+                        let Some((pallet, name)) = event.split_once('.') else {
+                            return true;
+                        };
+                        !metadata
+                            .pallet_by_name(pallet)
+                            .and_then(|pallet| pallet.event_variants())
+                            .map_or(false, |variants| variants.iter().any(|variant| variant.name == name))
+                    })
+                    .cloned()
+                    .collect();
+                for event in &missing {
+                    log::error!("Signing event {} is not in the runtime metadata, so will not be recorded", event);
+                }
+                self.store.set_missing_events(missing);
+            }
+
+            /// Record the signature events in a block, given the unix time in seconds of the block,
+            /// deleting old ones every so often
+            pub async fn observe(
+                &self,
+                block: &Block<EntropyConfig, OnlineClient<EntropyConfig>>,
+                time: u64,
+                snapshots: &SnapshotCache,
+            ) -> anyhow::Result<()> {
+                if block.number() % PRUNE_EVERY_BLOCKS == 0 {
+                    if let Err(error) = self.store.prune().await {
+                        log::warn!("Failed to delete old signature events: {}", error);
+                    }
+                }
+                let found = signature_events(block, time, snapshots, &self.events).await?;
+                if !found.is_empty() {
+                    log::debug!("Found {} signature events in block #{}", found.len(), block.number());
+                    self.store.record(found).await?;
+                }
+                Ok(())
+            }
+        }
+
+        /// The signature events in a block, attributed to a registered account where possible
+        async fn signature_events(
+            block: &Block<EntropyConfig, OnlineClient<EntropyConfig>>,
+            time: u64,
+            snapshots: &SnapshotCache,
+            events: &[String],
+        ) -> anyhow::Result<Vec<SignatureEvent>> {
+            let snapshot = snapshots.get();
+            let mut found = Vec::new();
+            let mut extrinsics = None;
+            for event in block.events().await?.iter() {
+                let event = event?;
+                let name = format!("{}.{}", event.pallet_name(), event.variant_name());
+                if !events.contains(&name) {
+                    continue;
+                }
+                // The event may start with the account the signature is for
+                let registered = event
+                    .field_bytes()
+                    .get(..32)
+                    .and_then(|bytes| {
+                        snapshot.as_ref()?.accounts.iter().find(|account| account.account_id.0 == bytes)
+                    });
+                let account_id = match (registered, event.phase()) {
+                    (Some(account), _) => Some(account.account_id.to_string()),
+                    // Otherwise it is whoever submitted the extrinsic which emitted it
+                    (None, Phase::ApplyExtrinsic(index)) => {
+                        if extrinsics.is_none() {
+                            extrinsics = Some(block.extrinsics().await?);
+                        }
+                        extrinsics
+                            .as_ref()
+                            .and_then(|extrinsics| extrinsics.iter().nth(index as usize))
+                            .and_then(|extrinsic| extrinsic.ok())
+                            .and_then(|extrinsic| extrinsic.address_bytes().and_then(decode_signer))
+                    }
+                    (None, _) => None,
+                };
+                let programs = account_id
+                    .as_ref()
+                    .and_then(|account_id| {
+                        snapshot
+                            .as_ref()?
+                            .accounts
+                            .iter()
+                            .find(|account| &account.account_id.to_string() == account_id)
+                    })
+                    .map(|account| account.program_pointers.clone())
+                    .unwrap_or_default();
+                found.push(SignatureEvent {
+                    time,
+                    block: block.number(),
+                    account_id,
+                    pallet: event.pallet_name().to_string(),
+                    event: event.variant_name().to_string(),
+                    programs,
+                });
+            }
+            Ok(found)
+        }
+    }
+}
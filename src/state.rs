@@ -0,0 +1,32 @@
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    use crate::{announcement::AnnouncementStore, network::Networks};
+    use axum::extract::FromRef;
+    use leptos::*;
+
+    /// Shared state for the axum router
+    #[derive(Clone)]
+    pub struct AppState {
+        pub leptos_options: LeptosOptions,
+        pub networks: Networks,
+        pub announcements: AnnouncementStore,
+        /// Token required by the admin API, which is disabled without one
+        pub admin_token: Option<String>,
+    }
+
+    impl AppState {
+        /// Make the shared state available to server functions and components through the
+        /// leptos context
+        pub fn provide(&self) {
+            provide_context(self.networks.clone());
+            provide_context(self.announcements.clone());
+        }
+    }
+
+    impl FromRef<AppState> for LeptosOptions {
+        fn from_ref(state: &AppState) -> Self {
+            state.leptos_options.clone()
+        }
+    }
+}}
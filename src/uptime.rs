@@ -0,0 +1,519 @@
+use crate::{
+    display_duration, display_timestamp,
+    network::{network_href, use_network},
+    DetailsTable, DisplayValue,
+};
+use leptos::*;
+use leptos_router::A;
+use serde::{Deserialize, Serialize};
+
+/// Number of days shown in the uptime bars
+pub const UPTIME_DAYS: u64 = 90;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// How severe the current problems with the network are, from least to most
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum StatusLevel {
+    Operational,
+    /// Something is wrong, but during planned maintenance
+    Maintenance,
+    Degraded,
+    Outage,
+}
+
+/// The status of the network as a whole, from the latest health checks
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OverallStatus {
+    pub level: StatusLevel,
+    /// What is wrong, if anything
+    pub problems: Vec<String>,
+}
+
+/// The share of health checks a component passed on one day
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DayUptime {
+    /// Unix time in seconds of the start of the day
+    pub day: u64,
+    /// Missing when there were no checks that day
+    pub availability: Option<f64>,
+}
+
+/// Daily availability of one monitored component
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ComponentUptime {
+    pub name: String,
+    /// Page with more detail about the component, within the network's pages
+    pub href: Option<String>,
+    pub days: Vec<DayUptime>,
+    /// Availability over all of `days`
+    pub availability: Option<f64>,
+}
+
+/// A period during which a component failed its health checks
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Incident {
+    pub component: String,
+    /// Unix time in seconds of the first failed check
+    pub started_at: u64,
+    /// Unix time in seconds of the first passed check afterwards, if it has recovered
+    pub ended_at: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UptimeReport {
+    pub components: Vec<ComponentUptime>,
+    /// Most recent first
+    pub incidents: Vec<Incident>,
+}
+
+#[server(GetOverallStatus, "/api")]
+pub async fn get_overall_status(network: String) -> Result<OverallStatus, ServerFnError> {
+    use crate::{announcement_store, network_state};
+
+    let network = network_state(&network)?;
+    Ok(overall_status(
+        &network.chain,
+        &network.snapshots,
+        &network.probes,
+        announcement_store()?.current_maintenance(&network.name),
+    ))
+}
+
+#[server(GetUptimeReport, "/api")]
+pub async fn get_uptime_report(network: String) -> Result<UptimeReport, ServerFnError> {
+    query_uptime_report(&crate::history_store(&network)?)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// A headline summarising the state of the whole network, linking to the uptime history
+#[component]
+pub fn StatusHeadline(status: OverallStatus) -> impl IntoView {
+    let (headline, color) = match status.level {
+        StatusLevel::Operational => ("All systems operational", "green"),
+        StatusLevel::Maintenance => ("Scheduled maintenance in progress", "blue"),
+        StatusLevel::Degraded => ("Degraded performance", "amber"),
+        StatusLevel::Outage => ("Outage", "red"),
+    };
+    view! {
+        <div class=format!("my-4 p-4 rounded-xl bg-{}-500/20 text-{}-900", color, color)>
+            <div class="flex items-center justify-between">
+                <p class="font-bold">{headline}</p>
+                <A href=network_href("/history") class="text-sm underline">
+                    "Uptime history"
+                </A>
+            </div>
+            <ul class="text-sm">
+                {status.problems.into_iter().map(|problem| view! { <li>{problem}</li> }).collect_view()}
+            </ul>
+        </div>
+    }
+}
+
+#[component]
+pub fn HistoryPage() -> impl IntoView {
+    let network = use_network();
+    let status = create_resource(move || network.get(), get_overall_status);
+    let report = create_resource(move || network.get(), get_uptime_report);
+    view! {
+        <div class="container mx-auto">
+            <A href=network_href("") class="text-sm underline">
+                "Back to status page"
+            </A>
+            <Transition fallback=|| ()>
+                {move || {
+                    status.get().and_then(|status| status.ok()).map(|status| view! { <StatusHeadline status/> })
+                }}
+
+            </Transition>
+            <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                {move || {
+                    report
+                        .get()
+                        .map(|report| match report {
+                            Err(e) => {
+                                view! { <pre class="error">"server error: " {e.to_string()}</pre> }
+                                    .into_view()
+                            }
+                            Ok(report) => view! { <UptimeReport report/> }.into_view(),
+                        })
+                        .unwrap_or_default()
+                }}
+
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+fn UptimeReport(report: UptimeReport) -> impl IntoView {
+    let incidents = if report.incidents.is_empty() {
+        view! {
+            <tr>
+                <td class="p-4">"No incidents recorded."</td>
+            </tr>
+        }
+            .into_view()
+    } else {
+        report
+            .incidents
+            .into_iter()
+            .map(|incident| {
+                view! {
+                    <tr class="hover:bg-gray-200">
+                        <DisplayValue value=incident.component long_value=None/>
+                        <DisplayValue value=display_timestamp(incident.started_at) long_value=None/>
+                        <DisplayValue
+                            value=incident.ended_at.map(display_timestamp).unwrap_or("Ongoing".to_string())
+                            long_value=None
+                        />
+                        <DisplayValue
+                            value=incident
+                                .ended_at
+                                .map(|ended_at| display_duration(ended_at.saturating_sub(incident.started_at)))
+                                .unwrap_or_default()
+                            long_value=None
+                        />
+                    </tr>
+                }
+            })
+            .collect_view()
+    };
+    view! {
+        <h2 class="my-4 block font-sans text-xl antialiased leading-snug tracking-normal mt-4 text-gray-700">
+            {format!("Uptime over the last {} days", UPTIME_DAYS)}
+        </h2>
+        <div class="flex flex-col gap-4 p-4 text-blue-gray-900 bg-white shadow-md rounded-xl bg-clip-border">
+            {report
+                .components
+                .into_iter()
+                .map(|component| view! { <UptimeBars component/> })
+                .collect_view()}
+        </div>
+        <DetailsTable title="Incidents" headings=vec!["Component", "Started", "Ended", "Duration"]>
+            {incidents}
+        </DetailsTable>
+    }
+}
+
+/// One bar per day, coloured by the share of checks passed
+#[component]
+fn UptimeBars(component: ComponentUptime) -> impl IntoView {
+    let name = match component.href {
+        Some(href) => view! {
+            <A href=network_href(&href) class="underline">
+                {component.name}
+            </A>
+        }
+        .into_view(),
+        None => component.name.into_view(),
+    };
+    view! {
+        <div>
+            <div class="flex justify-between text-sm">
+                <p>{name}</p>
+                <p>
+                    {component
+                        .availability
+                        .map(|availability| format!("{:.2}% uptime", availability * 100.0))
+                        .unwrap_or("No data".to_string())}
+                </p>
+            </div>
+            <div class="flex gap-px h-8">
+                {component
+                    .days
+                    .into_iter()
+                    .map(|day| {
+                        let color = match day.availability {
+                            None => "bg-gray-200",
+                            Some(availability) if availability >= 0.999 => "bg-green-500",
+                            Some(availability) if availability >= 0.95 => "bg-amber-500",
+                            Some(_) => "bg-red-500",
+                        };
+                        let title = format!(
+                            "{}: {}",
+                            display_date(day.day),
+                            day
+                                .availability
+                                .map(|availability| format!("{:.2}%", availability * 100.0))
+                                .unwrap_or("No data".to_string()),
+                        );
+                        view! { <div class=format!("flex-1 rounded-sm {}", color) title=title></div> }
+                    })
+                    .collect_view()}
+            </div>
+        </div>
+    }
+}
+
+fn display_date(seconds: u64) -> String {
+    match chrono::DateTime::from_timestamp(seconds as i64, 0) {
+        Some(time) => time.format("%Y-%m-%d").to_string(),
+        None => seconds.to_string(),
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::{
+            announcement::Announcement,
+            chain::{unix_now, ChainClient},
+            history::HistoryStore,
+            indexer::SnapshotCache,
+            probe::ProbeCache,
+        };
+        use rusqlite::params;
+        use std::{collections::BTreeMap, time::Duration};
+
+        /// How long without a newly indexed finalized block before block production counts as
+        /// stalled
+        const STALLED_AFTER: Duration = Duration::from_secs(2 * 60);
+
+        /// Most incidents listed
+        const MAX_INCIDENTS: usize = 50;
+
+        /// Work out the network status from the chain connection, indexer and probe results.
+        /// Problems during a maintenance window are expected, so are not reported as an outage.
+        pub fn overall_status(
+            chain: &ChainClient,
+            snapshots: &SnapshotCache,
+            probes: &ProbeCache,
+            maintenance: Option<Announcement>,
+        ) -> OverallStatus {
+            let mut level = StatusLevel::Operational;
+            let mut problems = Vec::new();
+
+            // Until the first connection attempt has finished nothing is known about the chain
+            let status = chain.status();
+            if !status.is_connecting() {
+                if !status.is_reachable() {
+                    level = StatusLevel::Outage;
+                    problems.push("The chain endpoint cannot be reached".to_string());
+                } else if !status.is_connected() {
+                    level = StatusLevel::Degraded;
+                    problems.push("The chain endpoint is only responding after retries".to_string());
+                }
+
+                let stalled = snapshots
+                    .last_updated()
+                    .map_or(true, |updated| unix_now().saturating_sub(updated) > STALLED_AFTER.as_secs());
+                if stalled {
+                    level = level.max(StatusLevel::Degraded);
+                    problems.push("No new finalized blocks".to_string());
+                }
+            }
+
+            if let Some(snapshot) = snapshots.get() {
+                let down = snapshot
+                    .validators
+                    .iter()
+                    .filter(|validator| probes.get(validator).map_or(false, |probe| !probe.reachable))
+                    .count();
+                if down > 0 && down == snapshot.validators.len() {
+                    level = StatusLevel::Outage;
+                    problems.push("No threshold servers are responding".to_string());
+                } else if down > 0 {
+                    level = level.max(StatusLevel::Degraded);
+                    problems.push(format!("{} of {} threshold servers are not responding", down, snapshot.validators.len()));
+                }
+            }
+
+            if let Some(maintenance) = maintenance {
+                if level != StatusLevel::Operational {
+                    level = StatusLevel::Maintenance;
+                    problems.insert(0, maintenance.title);
+                }
+            }
+
+            OverallStatus { level, problems }
+        }
+
+        /// Work out daily uptime and incidents from the recorded history
+        pub async fn query_uptime_report(store: &HistoryStore) -> anyhow::Result<UptimeReport> {
+            let now = unix_now();
+            let since = (now / SECONDS_PER_DAY + 1).saturating_sub(UPTIME_DAYS) * SECONDS_PER_DAY;
+
+            let network = store.network().to_string();
+            let (snapshots, probes) = store
+                .database()
+                .with_connection(move |connection| {
+                    let snapshots = connection
+                        .prepare("SELECT time, block, chain_up FROM snapshots WHERE network = ?1 AND time >= ?2 ORDER BY time")?
+                        .query_map(params![network, since], |row| Ok((row.get::<_, u64>(0)?, row.get::<_, Option<u32>>(1)?, row.get::<_, bool>(2)?)))?
+                        .collect::<rusqlite::Result<Vec<_>>>()?;
+                    let probes = connection
+                        .prepare(
+                            "SELECT time, stash_account, endpoint, reachable FROM probes
+                            WHERE network = ?1 AND time >= ?2 ORDER BY time",
+                        )?
+                        .query_map(params![network, since], |row| {
+                            Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, bool>(3)?))
+                        })?
+                        .collect::<rusqlite::Result<Vec<_>>>()?;
+                    Ok((snapshots, probes))
+                })
+                .await?;
+
+            let chain_checks: Vec<(u64, bool)> = snapshots.iter().map(|(time, _, chain_up)| (*time, *chain_up)).collect();
+            // Blocks are being produced if the finalized block has moved on since the last sample
+            let block_checks: Vec<(u64, bool)> = snapshots
+                .iter()
+                .filter_map(|(time, block, _)| block.map(|block| (*time, block)))
+                .scan(None, |previous, (time, block)| {
+                    let advanced = previous.map(|previous| block > previous);
+                    *previous = Some(block);
+                    Some(advanced.map(|advanced| (time, advanced)))
+                })
+                .flatten()
+                .collect();
+            let mut validator_checks: BTreeMap<(String, String), Vec<(u64, bool)>> = BTreeMap::new();
+            for (time, stash_account, endpoint, reachable) in probes {
+                validator_checks.entry((stash_account, endpoint)).or_default().push((time, reachable));
+            }
+
+            let mut components = vec![
+                summarize("Chain RPC".to_string(), None, since, &chain_checks),
+                summarize("Block production".to_string(), None, since, &block_checks),
+            ];
+            components.extend(validator_checks.iter().map(|((stash_account, endpoint), checks)| {
+                summarize(
+                    format!("Threshold server {}", endpoint),
+                    Some(format!("/validator/{}", stash_account)),
+                    since,
+                    checks,
+                )
+            }));
+
+            let mut incidents = find_incidents("Chain RPC", &chain_checks);
+            incidents.extend(find_incidents("Block production", &block_checks));
+            for ((_, endpoint), checks) in &validator_checks {
+                incidents.extend(find_incidents(&format!("Threshold server {}", endpoint), checks));
+            }
+            incidents.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+            incidents.truncate(MAX_INCIDENTS);
+
+            Ok(UptimeReport { components, incidents })
+        }
+
+        /// Daily availability from time ordered checks
+        fn summarize(name: String, href: Option<String>, since: u64, checks: &[(u64, bool)]) -> ComponentUptime {
+            let mut days: BTreeMap<u64, (u64, u64)> = BTreeMap::new();
+            for &(time, up) in checks {
+                let (passed, total) = days.entry(time / SECONDS_PER_DAY).or_default();
+                *passed += up as u64;
+                *total += 1;
+            }
+            let passed = checks.iter().filter(|(_, up)| *up).count();
+            ComponentUptime {
+                name,
+                href,
+                days: (0..UPTIME_DAYS)
+                    .map(|offset| {
+                        let day = since / SECONDS_PER_DAY + offset;
+                        DayUptime {
+                            day: day * SECONDS_PER_DAY,
+                            availability: days.get(&day).map(|(passed, total)| *passed as f64 / *total as f64),
+                        }
+                    })
+                    .collect(),
+                availability: (!checks.is_empty()).then(|| passed as f64 / checks.len() as f64),
+            }
+        }
+
+        /// Each run of failed checks is an incident
+        fn find_incidents(component: &str, checks: &[(u64, bool)]) -> Vec<Incident> {
+            let mut incidents = Vec::new();
+            let mut started_at = None;
+            for &(time, up) in checks {
+                match (started_at, up) {
+                    (None, false) => started_at = Some(time),
+                    (Some(started), true) => {
+                        incidents.push(Incident {
+                            component: component.to_string(),
+                            started_at: started,
+                            ended_at: Some(time),
+                        });
+                        started_at = None;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(started_at) = started_at {
+                incidents.push(Incident {
+                    component: component.to_string(),
+                    started_at,
+                    ended_at: None,
+                });
+            }
+            incidents
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            const DAY: u64 = SECONDS_PER_DAY;
+
+            fn incident(started_at: u64, ended_at: Option<u64>) -> Incident {
+                Incident {
+                    component: "Chain RPC".to_string(),
+                    started_at,
+                    ended_at,
+                }
+            }
+
+            #[test]
+            fn passing_checks_are_not_incidents() {
+                assert!(find_incidents("Chain RPC", &[]).is_empty());
+                assert!(find_incidents("Chain RPC", &[(100, true), (200, true)]).is_empty());
+            }
+
+            #[test]
+            fn run_of_failed_checks_is_one_incident() {
+                let checks = [(100, true), (200, false), (300, false), (400, true), (500, true)];
+                assert_eq!(find_incidents("Chain RPC", &checks), vec![incident(200, Some(400))]);
+            }
+
+            #[test]
+            fn incident_without_recovery_is_ongoing() {
+                let checks = [(100, false), (200, true), (300, false), (400, false)];
+                assert_eq!(
+                    find_incidents("Chain RPC", &checks),
+                    vec![incident(100, Some(200)), incident(300, None)]
+                );
+            }
+
+            #[test]
+            fn summarizes_availability_by_day() {
+                let since = 10 * DAY;
+                let checks = [(since, true), (since + 60, false), (since + DAY, true), (since + 3 * DAY + 5, false)];
+                let uptime = summarize("Chain RPC".to_string(), None, since, &checks);
+                assert_eq!(uptime.days.len() as u64, UPTIME_DAYS);
+                assert_eq!(uptime.days[0], DayUptime { day: since, availability: Some(0.5) });
+                assert_eq!(uptime.days[1], DayUptime { day: since + DAY, availability: Some(1.0) });
+                assert_eq!(uptime.days[2].availability, None);
+                assert_eq!(uptime.days[3].availability, Some(0.0));
+                assert_eq!(uptime.availability, Some(0.5));
+            }
+
+            #[test]
+            fn no_checks_have_unknown_availability() {
+                let uptime = summarize("Chain RPC".to_string(), None, 10 * DAY, &[]);
+                assert!(uptime.days.iter().all(|day| day.availability.is_none()));
+                assert_eq!(uptime.availability, None);
+            }
+
+            #[test]
+            fn not_an_outage_before_connecting() {
+                let chain = ChainClient::new(
+                    "ws://127.0.0.1:9944".to_string(),
+                    Duration::from_secs(5),
+                    Duration::from_secs(30),
+                );
+                let status = overall_status(&chain, &SnapshotCache::default(), &ProbeCache::default(), None);
+                assert_eq!(status, OverallStatus { level: StatusLevel::Operational, problems: Vec::new() });
+            }
+        }
+    }
+}
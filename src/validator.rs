@@ -1,90 +1,310 @@
-use crate::{DisplayValue, HexVec};
+use crate::{
+    indexer::{use_block, with_block, BlockRef},
+    network::{network_href, use_network},
+    probe::{KeyCheckStatus, ProbeResult, ProbeStatus},
+    DetailRow, DetailsList, DisplayValue, HexVec, LinkedValue,
+};
 use leptos::*;
+use leptos_router::*;
 use serde::{Deserialize, Serialize};
 use subxt::utils::AccountId32;
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
 pub struct Validator {
-    tss_account: AccountId32,
-    x25519_public_key: HexVec,
-    endpoint: String,
+    #[cfg_attr(feature = "ssr", schema(value_type = String))]
+    pub stash_account: AccountId32,
+    #[cfg_attr(feature = "ssr", schema(value_type = String))]
+    pub tss_account: AccountId32,
+    pub x25519_public_key: HexVec,
+    pub endpoint: String,
+    /// The latest check of the threshold server at `endpoint`
+    pub probe: Option<ProbeResult>,
 }
 
 #[component]
 pub fn Validator(validator: Validator) -> impl IntoView {
     view! {
         <tr class="hover:bg-gray-200">
+            <LinkedValue
+                value=validator.stash_account.to_string()
+                href=with_block(network_href(&format!("/validator/{}", validator.stash_account)))
+            />
             <DisplayValue value=validator.tss_account.to_string() long_value=None/>
             <DisplayValue
                 value=validator.x25519_public_key.to_string()
                 long_value=Some(format!("{:?}", validator.x25519_public_key))
             />
+            <KeyCheckStatus key_check=validator.probe.as_ref().map(|probe| probe.key_check.clone())/>
             <DisplayValue value=validator.endpoint long_value=None/>
+            <ProbeStatus probe=validator.probe/>
         </tr>
     }
 }
 
+/// Everything we know about a validator, including its staking information
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorDetails {
+    pub validator: Validator,
+    pub controller_account: Option<AccountId32>,
+    /// Total bonded, in the smallest unit
+    pub bonded: Option<u128>,
+    /// Commission in parts per billion
+    pub commission: Option<u32>,
+    pub session_keys: Option<HexVec>,
+    /// Whether the validator is in the active set this era
+    pub active: bool,
+    pub token_decimals: u32,
+    pub token_symbol: String,
+    pub block: BlockRef,
+}
+
+impl ValidatorDetails {
+    /// Display an amount in the smallest unit with the chain's token decimals and symbol. With
+    /// more decimals than a `u128` can hold, the amount is shown in the smallest unit.
+    pub fn display_amount(&self, amount: u128) -> String {
+        let Some(divisor) = 10u128.checked_pow(self.token_decimals) else {
+            return format!("{} (smallest unit of {})", amount, self.token_symbol);
+        };
+        let fraction = format!("{:0width$}", amount % divisor, width = self.token_decimals as usize);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            format!("{} {}", amount / divisor, self.token_symbol)
+        } else {
+            format!("{}.{} {}", amount / divisor, fraction, self.token_symbol)
+        }
+    }
+}
+
+#[server(GetValidatorDetails, "/api")]
+pub async fn get_validator_details(
+    network: String,
+    stash: String,
+    block: Option<String>,
+) -> Result<Option<ValidatorDetails>, ServerFnError> {
+    use crate::{get_api_rpc, snapshot_at};
+    use std::str::FromStr;
+
+    let stash_account = AccountId32::from_str(&stash)
+        .map_err(|_| ServerFnError::Args(format!("Invalid account ID {}", stash)))?;
+    let snapshot = snapshot_at(&network, block).await?;
+    let validator = match snapshot
+        .validators
+        .iter()
+        .find(|validator| validator.stash_account == stash_account)
+    {
+        Some(validator) => validator.clone(),
+        None => return Ok(None),
+    };
+    let (api, rpc) = get_api_rpc(&network).await?;
+
+    query_validator_details(&api, &rpc, validator, snapshot.block.clone())
+        .await
+        .map(Some)
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[component]
+pub fn ValidatorPage() -> impl IntoView {
+    let network = use_network();
+    let block = use_block();
+    let params = use_params_map();
+    let details = create_resource(
+        move || (network.get(), params.with(|params| params.get("stash").cloned().unwrap_or_default()), block.get()),
+        |(network, stash, block)| get_validator_details(network, stash, block),
+    );
+    view! {
+        <div class="container mx-auto">
+            <A href=with_block(network_href("")) class="text-sm underline">
+                "Back to status page"
+            </A>
+            <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                {move || {
+                    details
+                        .get()
+                        .map(move |details| match details {
+                            Err(e) => {
+                                view! { <pre class="error">"server error: " {e.to_string()}</pre> }
+                                    .into_view()
+                            }
+                            Ok(None) => view! { <p class="my-4">"This account is not a validator."</p> }.into_view(),
+                            Ok(Some(details)) => view! { <ValidatorDetails details/> }.into_view(),
+                        })
+                        .unwrap_or_default()
+                }}
+
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+pub fn ValidatorDetails(details: ValidatorDetails) -> impl IntoView {
+    let bonded = details
+        .bonded
+        .map(|bonded| details.display_amount(bonded))
+        .unwrap_or("Not bonded".to_string());
+    let validator = details.validator;
+    view! {
+        <DetailsList title=format!("Validator {}", validator.stash_account)>
+            <DetailRow label="Stash Account">
+                <DisplayValue value=validator.stash_account.to_string() long_value=None/>
+            </DetailRow>
+            <DetailRow label="Controller Account">
+                <DisplayValue
+                    value=details.controller_account.map(|account| account.to_string()).unwrap_or_default()
+                    long_value=None
+                />
+            </DetailRow>
+            <DetailRow label="TSS Account">
+                <DisplayValue value=validator.tss_account.to_string() long_value=None/>
+            </DetailRow>
+            <DetailRow label="X25519 Public Key">
+                <DisplayValue value=format!("0x{:?}", validator.x25519_public_key) long_value=None/>
+            </DetailRow>
+            <DetailRow label="Advertised Key Check">
+                <KeyCheckStatus key_check=validator.probe.as_ref().map(|probe| probe.key_check.clone())/>
+            </DetailRow>
+            <DetailRow label="Socket Address">
+                <DisplayValue value=validator.endpoint long_value=None/>
+            </DetailRow>
+            <DetailRow label="Threshold Server Version">
+                <DisplayValue
+                    value=validator
+                        .probe
+                        .as_ref()
+                        .and_then(|probe| probe.version.clone())
+                        .unwrap_or("Unknown".to_string())
+                    long_value=None
+                />
+            </DetailRow>
+            <DetailRow label="Bonded">
+                <DisplayValue value=bonded long_value=None/>
+            </DetailRow>
+            <DetailRow label="Commission">
+                <DisplayValue
+                    value=details
+                        .commission
+                        .map(|parts| format!("{}%", parts as f64 / 10_000_000.0))
+                        .unwrap_or_default()
+                    long_value=None
+                />
+            </DetailRow>
+            <DetailRow label="Session Keys">
+                <DisplayValue
+                    value=details.session_keys.map(|keys| format!("0x{:?}", keys)).unwrap_or_default()
+                    long_value=None
+                />
+            </DetailRow>
+            <DetailRow label="Active This Era">
+                <DisplayValue value=details.active.to_string() long_value=None/>
+            </DetailRow>
+            <DetailRow label="Read at Block">
+                <DisplayValue
+                    value=format!("#{}", details.block.number)
+                    long_value=Some(details.block.hash)
+                />
+            </DetailRow>
+        </DetailsList>
+    }
+}
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "ssr")] {
         use entropy_testing_utils::{
-            chain_api::entropy::runtime_types::pallet_staking_extension::pallet::ServerInfo,
+            chain_api::{entropy, entropy::runtime_types::pallet_staking_extension::pallet::ServerInfo, EntropyConfig},
         };
+        use parity_scale_codec::{Decode, Encode};
+        use subxt::{backend::legacy::LegacyRpcMethods, utils::H256, Config, OnlineClient};
 
         impl Validator {
-            fn new(_stash_account: AccountId32, server_info: ServerInfo<AccountId32>) -> Validator {
+            fn new(stash_account: AccountId32, server_info: ServerInfo<AccountId32>) -> Validator {
                 Validator {
+                    stash_account,
                     tss_account: server_info.tss_account,
                     x25519_public_key: HexVec(server_info.x25519_public_key.to_vec()),
                     endpoint: String::from_utf8(server_info.endpoint).unwrap_or("Cannot decode UTF8".to_string()),
+                    probe: None,
                 }
             }
         }
-    }
-}
 
-#[server(GetValidators, "/api")]
-pub async fn get_validators() -> Result<Vec<Validator>, ServerFnError> {
-    use crate::get_api_rpc;
-    use anyhow::anyhow;
-    use entropy_testing_utils::chain_api::EntropyConfig;
-    use parity_scale_codec::Decode;
-    use subxt::{backend::legacy::LegacyRpcMethods, Config, OnlineClient};
-
-    async fn get_validators_internal(
-        api: &OnlineClient<EntropyConfig>,
-        rpc: &LegacyRpcMethods<EntropyConfig>,
-    ) -> anyhow::Result<
-        Vec<(
-            AccountId32,
-            ServerInfo<<EntropyConfig as Config>::AccountId>,
-        )>,
-    > {
-        let block_hash = rpc
-            .chain_get_block_hash(None)
-            .await?
-            .ok_or_else(|| anyhow!("Error getting block hash"))?;
-        let keys = Vec::<()>::new();
-        let storage_address = subxt::dynamic::storage("StakingExtension", "ThresholdServers", keys);
-        let mut iter = api.storage().at(block_hash).iter(storage_address).await?;
-        let mut validators = Vec::new();
-        while let Some(Ok((storage_key, account))) = iter.next().await {
-            let decoded = account.into_encoded();
-            let server_info: ServerInfo<<EntropyConfig as Config>::AccountId> =
-                ServerInfo::decode(&mut decoded.as_ref())?;
-            let key: [u8; 32] = storage_key[storage_key.len() - 32..].try_into()?;
-            validators.push((AccountId32(key), server_info))
+        /// Read the set of threshold servers at the given block
+        pub async fn query_validators(
+            api: &OnlineClient<EntropyConfig>,
+            block_hash: H256,
+        ) -> anyhow::Result<Vec<Validator>> {
+            let keys = Vec::<()>::new();
+            let storage_address = subxt::dynamic::storage("StakingExtension", "ThresholdServers", keys);
+            let mut iter = api.storage().at(block_hash).iter(storage_address).await?;
+            let mut validators = Vec::new();
+            while let Some(item) = iter.next().await {
+                let (storage_key, account) = item?;
+                let decoded = account.into_encoded();
+                let server_info: ServerInfo<<EntropyConfig as Config>::AccountId> =
+                    ServerInfo::decode(&mut decoded.as_ref())?;
+                let key: [u8; 32] = storage_key[storage_key.len() - 32..].try_into()?;
+                validators.push(Validator::new(AccountId32(key), server_info))
+            }
+            Ok(validators)
         }
-        Ok(validators)
-    }
 
-    let (api, rpc) = get_api_rpc().await?;
+        /// Read the staking information for a validator at the given block
+        pub async fn query_validator_details(
+            api: &OnlineClient<EntropyConfig>,
+            rpc: &LegacyRpcMethods<EntropyConfig>,
+            validator: Validator,
+            block: BlockRef,
+        ) -> anyhow::Result<ValidatorDetails> {
+            let storage = api.storage().at(block.block_hash()?);
+            let stash = &validator.stash_account;
 
-    let validators = get_validators_internal(&api, &rpc)
-        .await
-        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
-        .into_iter()
-        .map(|(stash_account, server_info)| Validator::new(stash_account, server_info))
-        .collect();
+            let controller_account = storage.fetch(&entropy::storage().staking().bonded(stash)).await?;
+            let bonded = match &controller_account {
+                Some(controller) => storage
+                    .fetch(&entropy::storage().staking().ledger(controller))
+                    .await?
+                    .map(|ledger| ledger.total),
+                None => None,
+            };
+            let commission = storage
+                .fetch(&entropy::storage().staking().validators(stash))
+                .await?
+                .map(|prefs| prefs.commission.0);
+            let session_keys = storage
+                .fetch(&entropy::storage().session().next_keys(stash))
+                .await?
+                .map(|keys| HexVec(keys.encode()));
+            let active = storage
+                .fetch_or_default(&entropy::storage().session().validators())
+                .await?
+                .contains(stash);
 
-    Ok(validators)
+            let properties = rpc.system_properties().await?;
+
+            Ok(ValidatorDetails {
+                controller_account,
+                bonded,
+                commission,
+                session_keys,
+                active,
+                token_decimals: properties
+                    .get("tokenDecimals")
+                    .and_then(|decimals| decimals.as_u64())
+                    .unwrap_or_default() as u32,
+                token_symbol: properties
+                    .get("tokenSymbol")
+                    .and_then(|symbol| symbol.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                validator,
+                block,
+            })
+        }
+    }
+}
+
+#[server(GetValidators, "/api")]
+pub async fn get_validators(network: String, block: Option<String>) -> Result<Vec<Validator>, ServerFnError> {
+    Ok(crate::snapshot_at(&network, block).await?.validators.clone())
 }